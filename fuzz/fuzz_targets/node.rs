@@ -0,0 +1,12 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    yellowstone_faithful_car_parser::node::{DecodeMode, Node},
+};
+
+fuzz_target!(|data: &[u8]| {
+    for mode in [DecodeMode::Lenient, DecodeMode::Strict] {
+        let _ = Node::decode(data, mode);
+    }
+});
@@ -0,0 +1,42 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    std::io::Cursor,
+    yellowstone_faithful_car_parser::node::{
+        DecodeMode, Node, NodeReader, NodeReaderOptions, Nodes,
+    },
+};
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("valid runtime");
+    runtime.block_on(async {
+        let mut reader = NodeReaderOptions::default()
+            .verify_cid(true)
+            .decode_mode(DecodeMode::Strict)
+            .build(Cursor::new(data));
+        while let Ok(nodes) = Nodes::read_until_block(&mut reader).await {
+            if nodes.nodes.is_empty() {
+                break;
+            }
+        }
+
+        // without CID verification frames can link to themselves or to each other
+        let mut reader = NodeReader::new(Cursor::new(data));
+        while let Ok(nodes) = Nodes::read_until_block(&mut reader).await {
+            if nodes.nodes.is_empty() {
+                break;
+            }
+            for node in nodes.nodes.values() {
+                if let Node::DataFrame(dataframe) = node {
+                    let _ = nodes.reassemble_dataframes(dataframe);
+                }
+            }
+        }
+
+        let mut reader = NodeReader::new(Cursor::new(data));
+        while let Ok(Some(_node)) = reader.read_node_recover(|_skipped| {}).await {}
+    });
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    yellowstone_faithful_car_parser::node::{Node, RawNode},
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(node) = RawNode::new_from_vec(data.to_vec()) {
+        let _ = Node::try_from(node.get_data());
+    }
+});
@@ -1,19 +1,41 @@
 use {
     anyhow::Context,
+    bytes::Bytes,
     clap::Parser,
     indicatif::{MultiProgress, ProgressBar, ProgressStyle},
     prost::Message,
-    serde::Deserialize,
-    solana_sdk::transaction::{TransactionError, VersionedTransaction},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        pubkey::Pubkey,
+        transaction::{TransactionError, VersionedTransaction},
+    },
     solana_storage_proto::convert::generated,
-    tokio::{fs::File, io::BufReader},
-    yellowstone_faithful_car_parser::node::{Node, NodeReader, Nodes},
+    std::{
+        io::{self, BufWriter, Write},
+        num::{NonZeroU64, NonZeroUsize},
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+    tokio::{
+        fs::{self, File},
+        io::BufReader,
+    },
+    yellowstone_faithful_car_parser::{
+        cache::NodeCache,
+        filter::AccountFilter,
+        gaps::GapReport,
+        node::{DecodeMode, Node, NodeReader, NodeReaderOptions, NodeWithCid, Nodes},
+        schedule::{DEFAULT_SLOTS_PER_EPOCH, EpochSchedule},
+    },
 };
 
+/// Number of blocks between checkpoint updates
+const CHECKPOINT_INTERVAL: u64 = 1_000;
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about = "count nodes in CAR files")]
 struct Args {
-    /// Path to CAR file
+    /// Path to CAR file, zstd or gzip compressed files are detected automatically
     #[clap(long)]
     pub car: String,
 
@@ -24,6 +46,47 @@ struct Args {
     /// Decode Nodes to Solana structs
     #[clap(long)]
     pub decode: bool,
+
+    /// Verify that data of every section matches its CID
+    #[clap(long)]
+    pub verify_cid: bool,
+
+    /// Fail on missing fields, out of range integers and extra fields instead of ignoring them
+    #[clap(long)]
+    pub strict: bool,
+
+    /// Print every Node as JSON line to stdout
+    #[clap(long)]
+    pub jsonl: bool,
+
+    /// Count transactions touching the account, can be used multiple times
+    #[clap(long, requires = "parse")]
+    pub account: Vec<Pubkey>,
+
+    /// Save offset of the last completed block to the file and resume from it on restart
+    /// (counters start from zero after resume)
+    #[clap(long, requires = "parse")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Number of slots per epoch of the archive cluster
+    #[clap(long, default_value_t = NonZeroU64::new(DEFAULT_SLOTS_PER_EPOCH).unwrap())]
+    pub slots_per_epoch: NonZeroU64,
+
+    /// Cluster epochs start short and grow to `--slots-per-epoch` (test clusters)
+    #[clap(long)]
+    pub warmup: bool,
+
+    /// Cache decoded nodes and reassembled payloads, number of entries of each cache
+    #[clap(long, requires = "parse")]
+    pub cache: Option<NonZeroUsize>,
+
+    /// Skip corrupted sections instead of failing
+    #[clap(long, requires = "parse")]
+    pub recover: bool,
+
+    /// Write skipped slots and blocks with unexpected parent slot as JSON to the file
+    #[clap(long, requires = "parse")]
+    pub gaps: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -33,7 +96,32 @@ async fn main() -> anyhow::Result<()> {
     let file = File::open(args.car)
         .await
         .context("failed to open CAR file")?;
-    let mut reader = NodeReader::new(BufReader::new(file));
+    let decode_mode = if args.strict {
+        DecodeMode::Strict
+    } else {
+        DecodeMode::Lenient
+    };
+    let cache = args.cache.map(|size| Arc::new(NodeCache::new(size, size)));
+    let mut options = NodeReaderOptions::default()
+        .verify_cid(args.verify_cid)
+        .decode_mode(decode_mode);
+    if let Some(cache) = &cache {
+        options = options.cache(Arc::clone(cache));
+    }
+    let mut reader = NodeReader::detect(BufReader::new(file), options)
+        .await
+        .context("failed to read CAR file")?;
+
+    if args.jsonl {
+        let mut stdout = BufWriter::new(io::stdout().lock());
+        while let Some(node) = reader.read_node().await? {
+            let node = NodeWithCid::decode(&node, decode_mode)?;
+            serde_json::to_writer(&mut stdout, &node).context("failed to serialize node")?;
+            stdout.write_all(b"\n")?;
+        }
+        stdout.flush()?;
+        return Ok(());
+    }
 
     if !args.parse {
         let bar = ProgressBar::no_length()
@@ -51,14 +139,41 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
-    let mut bar = ReaderProgressBar::new(args.decode);
-    let mut next_slot = None;
+    let filter = AccountFilter::new(args.account);
+    let mut bar = ReaderProgressBar::new(args.decode, !filter.is_empty(), args.recover);
+    let schedule = EpochSchedule::new(args.slots_per_epoch, args.warmup);
+    let mut gaps = None;
+    if let Some(path) = args.checkpoint.as_deref() {
+        if let Some(checkpoint) = Checkpoint::load(path).await? {
+            reader.seek(checkpoint.offset).await?;
+            gaps = Some(GapReport::after_block(checkpoint.slot));
+        }
+    }
+    let mut blocks_since_checkpoint = 0;
     loop {
-        let nodes = Nodes::read_until_block(&mut reader).await?;
+        let nodes = if args.recover {
+            Nodes::read_until_block_recover(&mut reader, |skip| {
+                bar.println(format!(
+                    "skipped {} bytes at offset {}: {}",
+                    skip.skipped, skip.offset, skip.error
+                ));
+                bar.skipped_bytes += skip.skipped;
+            })
+            .await?
+        } else {
+            Nodes::read_until_block(&mut reader).await?
+        };
         if nodes.nodes.is_empty() {
             break;
         }
 
+        if !filter.is_empty() {
+            for tx in filter.filter_block(&nodes)? {
+                tx.context("failed to filter transaction")?;
+                bar.transaction_matched += 1;
+            }
+        }
+
         for node in nodes.nodes.values() {
             match node {
                 Node::Transaction(frame) => {
@@ -70,13 +185,19 @@ async fn main() -> anyhow::Result<()> {
                     let _tx = bincode::deserialize::<VersionedTransaction>(&frame.data.data)
                         .context("failed to parse tx")?;
 
-                    let buffer = nodes
-                        .reassemble_dataframes(&frame.metadata)
-                        .context("failed to reassemble tx metadata")?;
+                    let buffer = match &cache {
+                        Some(cache) => {
+                            nodes.reassemble_dataframes_cached(&frame.metadata, cache, decode_mode)
+                        }
+                        None => nodes
+                            .reassemble_dataframes(&frame.metadata)
+                            .map(Bytes::from),
+                    }
+                    .context("failed to reassemble tx metadata")?;
                     if buffer.is_empty() {
                         bar.transaction_meta_empty += 1;
                     } else {
-                        let buffer = zstd::decode_all(buffer.as_slice())
+                        let buffer = zstd::decode_all(buffer.as_ref())
                             .context("failed to decompress tx metadata")?;
                         if decode_protobuf_bincode::<
                             StoredTransactionStatusMeta,
@@ -94,12 +215,18 @@ async fn main() -> anyhow::Result<()> {
                 Node::Block(frame) => {
                     bar.block += 1;
 
-                    let expected_slot = match next_slot {
-                        Some(slot) => slot,
-                        None => frame.slot - frame.slot % 432_000,
-                    };
-                    next_slot = Some(frame.slot + 1);
-                    bar.block_skippped += frame.slot - expected_slot;
+                    let gaps = gaps.get_or_insert_with(|| {
+                        GapReport::for_epoch(&schedule, schedule.get_epoch(frame.slot))
+                    });
+                    let mismatches = gaps.mismatches.len();
+                    gaps.push_block(frame);
+                    bar.block_skippped = gaps.skipped.len() as u64;
+                    if let Some(mismatch) = gaps.mismatches.get(mismatches) {
+                        bar.println(format!(
+                            "block {} has parent slot {}, previous block slot is {:?}",
+                            mismatch.slot, mismatch.parent_slot, mismatch.previous_slot
+                        ));
+                    }
                 }
                 Node::Subset(_) => bar.subset += 1,
                 Node::Epoch(_) => bar.epoch += 1,
@@ -109,16 +236,7 @@ async fn main() -> anyhow::Result<()> {
                         continue;
                     }
 
-                    let buffer = nodes
-                        .reassemble_dataframes(&frame.data)
-                        .context("failed to reassemble rewards")?;
-                    let buffer = zstd::decode_all(buffer.as_slice())
-                        .context("failed to decompress rewards")?;
-                    if decode_protobuf_bincode::<Vec<StoredBlockReward>, generated::Rewards>(
-                        "rewards", &buffer,
-                    )
-                    .is_ok()
-                    {
+                    if frame.decode_payload(&nodes).await.is_ok() {
                         bar.rewards_decode_ok += 1;
                     } else {
                         bar.rewards_decode_err += 1;
@@ -129,13 +247,72 @@ async fn main() -> anyhow::Result<()> {
         }
 
         bar.report();
+
+        blocks_since_checkpoint += 1;
+        if blocks_since_checkpoint >= CHECKPOINT_INTERVAL {
+            blocks_since_checkpoint = 0;
+            save_checkpoint(args.checkpoint.as_deref(), reader.offset(), gaps.as_ref()).await?;
+        }
     }
+    save_checkpoint(args.checkpoint.as_deref(), reader.offset(), gaps.as_ref()).await?;
     bar.finish();
 
+    if let (Some(path), Some(mut gaps)) = (args.gaps, gaps) {
+        // slots after the last block of the epoch are skipped by leaders too
+        if let Some(slot) = gaps.last_slot {
+            gaps.finish(schedule.get_last_slot_in_epoch(schedule.get_epoch(slot)));
+        }
+        fs::write(&path, serde_json::to_vec(&gaps)?)
+            .await
+            .context("failed to write gaps report")?;
+    }
+
     Ok(())
 }
 
+/// Position right after the last completed block
+#[derive(Debug, Deserialize, Serialize)]
+struct Checkpoint {
+    offset: u64,
+    slot: u64,
+}
+
+impl Checkpoint {
+    async fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        match fs::read(path).await {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .context("failed to parse checkpoint"),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).context("failed to read checkpoint"),
+        }
+    }
+
+    async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        // write to a temporary file first, so a crash never leaves a partial checkpoint
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)
+            .await
+            .context("failed to write checkpoint")?;
+        fs::rename(&tmp, path)
+            .await
+            .context("failed to write checkpoint")
+    }
+}
+
+async fn save_checkpoint(
+    path: Option<&Path>,
+    offset: u64,
+    gaps: Option<&GapReport>,
+) -> anyhow::Result<()> {
+    match (path, gaps.and_then(|gaps| gaps.last_slot)) {
+        (Some(path), Some(slot)) => Checkpoint { offset, slot }.save(path).await,
+        _ => Ok(()),
+    }
+}
+
 struct ReaderProgressBar {
+    multi: MultiProgress,
     transaction: u64,
     pb_transaction: ProgressBar,
     entry: u64,
@@ -164,10 +341,16 @@ struct ReaderProgressBar {
     pb_rewards_decode_ok: Option<ProgressBar>,
     rewards_decode_err: u64,
     pb_rewards_decode_err: Option<ProgressBar>,
+    //
+    transaction_matched: u64,
+    pb_transaction_matched: Option<ProgressBar>,
+    //
+    skipped_bytes: u64,
+    pb_skipped_bytes: Option<ProgressBar>,
 }
 
 impl ReaderProgressBar {
-    fn new(decode: bool) -> Self {
+    fn new(decode: bool, filter: bool, recover: bool) -> Self {
         let multi = MultiProgress::new();
         Self {
             transaction: 0,
@@ -203,6 +386,14 @@ impl ReaderProgressBar {
             rewards_decode_err: 0,
             pb_rewards_decode_err: decode
                 .then(|| Self::create_pbbar(&multi, "decoded/err", "rewards")),
+            //
+            transaction_matched: 0,
+            pb_transaction_matched: filter
+                .then(|| Self::create_pbbar(&multi, "matched", "transaction")),
+            //
+            skipped_bytes: 0,
+            pb_skipped_bytes: recover.then(|| Self::create_pbbar(&multi, "skipped", "bytes")),
+            multi,
         }
     }
 
@@ -215,6 +406,11 @@ impl ReaderProgressBar {
         pb
     }
 
+    fn println(&self, message: String) {
+        // printing fails only if stderr is not available, nothing to do then
+        let _ = self.multi.println(message);
+    }
+
     fn report(&self) {
         for (pb, pos) in [
             (Some(&self.pb_transaction), self.transaction),
@@ -241,6 +437,13 @@ impl ReaderProgressBar {
             ),
             (self.pb_rewards_decode_ok.as_ref(), self.rewards_decode_ok),
             (self.pb_rewards_decode_err.as_ref(), self.rewards_decode_err),
+            //
+            (
+                self.pb_transaction_matched.as_ref(),
+                self.transaction_matched,
+            ),
+            //
+            (self.pb_skipped_bytes.as_ref(), self.skipped_bytes),
         ] {
             if let Some(pb) = pb {
                 pb.set_position(pos);
@@ -265,6 +468,10 @@ impl ReaderProgressBar {
             self.pb_transaction_decode_err.as_ref(),
             self.pb_rewards_decode_ok.as_ref(),
             self.pb_rewards_decode_err.as_ref(),
+            //
+            self.pb_transaction_matched.as_ref(),
+            //
+            self.pb_skipped_bytes.as_ref(),
         ]
         .into_iter()
         .flatten()
@@ -300,10 +507,3 @@ struct StoredTransactionStatusMeta {
     pre_balances: Vec<u64>,
     post_balances: Vec<u64>,
 }
-
-#[allow(dead_code)]
-#[derive(Deserialize)]
-struct StoredBlockReward {
-    pubkey: String,
-    lamports: i64,
-}
@@ -0,0 +1,56 @@
+use {
+    anyhow::Context,
+    clap::Parser,
+    std::{num::NonZeroUsize, path::PathBuf},
+    tokio::{fs::File, io::BufReader},
+    yellowstone_faithful_car_parser::{
+        export::{DEFAULT_ROW_GROUP_SIZE, ParquetExporter},
+        node::{NodeReader, NodeReaderOptions, Nodes},
+    },
+};
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about = "export CAR file to Parquet tables")]
+struct Args {
+    /// Path to CAR file, zstd or gzip compressed files are detected automatically
+    #[clap(long)]
+    pub car: String,
+
+    /// Output directory for `blocks.parquet`, `transactions.parquet` and `rewards.parquet`
+    #[clap(long)]
+    pub out: PathBuf,
+
+    /// Max number of rows in the row group
+    #[clap(long, default_value_t = DEFAULT_ROW_GROUP_SIZE)]
+    pub row_group_size: NonZeroUsize,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let file = File::open(args.car)
+        .await
+        .context("failed to open CAR file")?;
+    let mut reader = NodeReader::detect(BufReader::new(file), NodeReaderOptions::default())
+        .await
+        .context("failed to read CAR file")?;
+
+    std::fs::create_dir_all(&args.out).context("failed to create output directory")?;
+    let mut exporter = ParquetExporter::create(&args.out, args.row_group_size)
+        .context("failed to create Parquet files")?;
+    loop {
+        let nodes = Nodes::read_until_block(&mut reader).await?;
+        if nodes.get_block().is_none() {
+            break;
+        }
+        exporter
+            .push_block(&nodes)
+            .context("failed to export block")?;
+    }
+    exporter
+        .finish()
+        .context("failed to finish Parquet files")?;
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use {
+    anyhow::Context,
+    clap::Parser,
+    std::{net::SocketAddr, path::PathBuf, sync::Arc},
+    tokio::{fs::File, io::BufReader, net::TcpListener},
+    yellowstone_faithful_car_parser::{
+        index::{SignatureIndex, SlotIndex},
+        rpc::RpcServer,
+        source::FileSource,
+    },
+};
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about = "historical JSON-RPC server over CAR file")]
+struct Args {
+    /// Path to CAR file
+    #[clap(long)]
+    pub car: PathBuf,
+
+    /// Address to listen on
+    #[clap(long, default_value = "127.0.0.1:8899")]
+    pub listen: SocketAddr,
+
+    /// Index account keys for `getSignaturesForAddress`
+    #[clap(long)]
+    pub index_addresses: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let file = File::open(&args.car)
+        .await
+        .context("failed to open CAR file")?;
+    let mut signatures = SignatureIndex::new(args.index_addresses);
+    let slots = SlotIndex::build(BufReader::new(file), |_block, nodes| {
+        signatures.push_block(nodes)
+    })
+    .await
+    .context("failed to index CAR file")?;
+    eprintln!(
+        "indexed {} blocks and {} transactions",
+        slots.blocks.len(),
+        signatures.transactions.len()
+    );
+
+    let server = Arc::new(RpcServer::new(FileSource::new(args.car), slots, signatures));
+
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .with_context(|| format!("failed to bind {}", args.listen))?;
+    eprintln!("listen on {}", args.listen);
+    server
+        .serve(listener, |error| {
+            eprintln!("failed to serve connection: {error}")
+        })
+        .await
+        .context("failed to accept connection")
+}
@@ -0,0 +1,184 @@
+use {
+    crate::node::{DecodeMode, Node, NodeError, NodeWithCid, RawNode},
+    bytes::Bytes,
+    cid::Cid,
+    lru::LruCache,
+    std::{
+        fmt,
+        num::NonZeroUsize,
+        sync::{
+            Arc, Mutex, MutexGuard, PoisonError,
+            atomic::{AtomicU64, Ordering},
+        },
+    },
+};
+
+/// Hits and misses counted since the cache was created
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheMetrics {
+    pub node_hits: u64,
+    pub node_misses: u64,
+    pub payload_hits: u64,
+    pub payload_misses: u64,
+}
+
+/// Bounded LRU cache of decoded nodes and reassembled dataframe payloads, can be shared by
+/// readers of overlapping CAR files
+pub struct NodeCache {
+    /// Nodes are keyed by the mode too, a node decoded in lenient mode is not a valid strict one
+    nodes: Mutex<LruCache<(Cid, DecodeMode), Arc<Node>>>,
+    /// Payloads keyed by `DataFrame::cid` of the first frame, which links the rest of the frames
+    payloads: Mutex<LruCache<Cid, Bytes>>,
+    node_hits: AtomicU64,
+    node_misses: AtomicU64,
+    payload_hits: AtomicU64,
+    payload_misses: AtomicU64,
+}
+
+impl fmt::Debug for NodeCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeCache")
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+impl NodeCache {
+    pub fn new(nodes: NonZeroUsize, payloads: NonZeroUsize) -> Self {
+        Self {
+            nodes: Mutex::new(LruCache::new(nodes)),
+            payloads: Mutex::new(LruCache::new(payloads)),
+            node_hits: AtomicU64::new(0),
+            node_misses: AtomicU64::new(0),
+            payload_hits: AtomicU64::new(0),
+            payload_misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Node decoded in the same `mode`, a node decoded in another mode is a miss
+    pub fn get_node(&self, cid: &Cid, mode: DecodeMode) -> Option<Arc<Node>> {
+        let node = lock(&self.nodes).get(&(*cid, mode)).map(Arc::clone);
+        count(&self.node_hits, &self.node_misses, node.is_some());
+        node
+    }
+
+    pub fn insert_node(&self, cid: Cid, mode: DecodeMode, node: Arc<Node>) {
+        lock(&self.nodes).put((cid, mode), node);
+    }
+
+    /// Decode the node only if it is not in the cache yet
+    pub fn decode(&self, node: &RawNode, mode: DecodeMode) -> Result<Arc<Node>, NodeError> {
+        if let Some(decoded) = self.get_node(&node.cid, mode) {
+            return Ok(decoded);
+        }
+
+        let decoded = Arc::new(NodeWithCid::decode(node, mode)?.node);
+        self.insert_node(node.cid, mode, Arc::clone(&decoded));
+        Ok(decoded)
+    }
+
+    /// Payload reassembled from the frames starting at the first frame with `cid`
+    pub fn get_payload(&self, cid: &Cid) -> Option<Bytes> {
+        let payload = lock(&self.payloads).get(cid).cloned();
+        count(&self.payload_hits, &self.payload_misses, payload.is_some());
+        payload
+    }
+
+    /// `payload` should be reassembled from the frames starting at the first frame with `cid`
+    /// and validated
+    pub fn insert_payload(&self, cid: Cid, payload: Bytes) {
+        lock(&self.payloads).put(cid, payload);
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            node_hits: self.node_hits.load(Ordering::Relaxed),
+            node_misses: self.node_misses.load(Ordering::Relaxed),
+            payload_hits: self.payload_hits.load(Ordering::Relaxed),
+            payload_misses: self.payload_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// entries are replaced as a whole, so a panic while the lock is held can not leave them broken
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn count(hits: &AtomicU64, misses: &AtomicU64, hit: bool) {
+    if hit { hits } else { misses }.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            cache::{CacheMetrics, NodeCache},
+            node::{DataFrame, DecodeMode, Node, Nodes, RawNode},
+            util::tests::{encode_cid, encode_epoch},
+        },
+        std::{num::NonZeroUsize, sync::Arc},
+    };
+
+    #[test]
+    fn test_cache() {
+        let cache = NodeCache::new(NonZeroUsize::new(2).unwrap(), NonZeroUsize::MIN);
+
+        let data = encode_epoch(1, &[]);
+        let node = RawNode::new(encode_cid(&data), data);
+        for _ in 0..2 {
+            let decoded = cache.decode(&node, DecodeMode::Lenient).unwrap();
+            assert!(matches!(decoded.as_ref(), Node::Epoch(epoch) if epoch.epoch == 1));
+        }
+        assert!(cache.get_node(&node.cid, DecodeMode::Strict).is_none());
+
+        // frames are missed in the block nodes, but were decoded before
+        let frame = |index, data: &[u8]| DataFrame {
+            index: Some(index),
+            total: Some(2),
+            data: data.to_vec(),
+            ..Default::default()
+        };
+        let next = encode_cid(b"frame");
+        cache.insert_node(
+            next,
+            DecodeMode::Lenient,
+            Arc::new(Node::DataFrame(frame(1, b"world"))),
+        );
+        let first = DataFrame {
+            next: vec![next],
+            ..frame(0, b"hello ")
+        };
+        for _ in 0..2 {
+            let payload = Nodes::default()
+                .reassemble_dataframes_cached(&first, &cache, DecodeMode::Lenient)
+                .unwrap();
+            assert_eq!(payload.as_ref(), b"hello world");
+        }
+
+        assert_eq!(
+            cache.get_payload(&first.cid()).as_deref(),
+            Some(b"hello world".as_slice())
+        );
+
+        // the same links after another first frame are another payload
+        let other = DataFrame {
+            next: vec![next],
+            ..frame(0, b"hi ")
+        };
+        let payload = Nodes::default()
+            .reassemble_dataframes_cached(&other, &cache, DecodeMode::Lenient)
+            .unwrap();
+        assert_eq!(payload.as_ref(), b"hi world");
+
+        assert_eq!(
+            cache.metrics(),
+            CacheMetrics {
+                node_hits: 3,
+                node_misses: 2,
+                payload_hits: 2,
+                payload_misses: 2,
+            }
+        );
+    }
+}
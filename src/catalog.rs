@@ -0,0 +1,322 @@
+use {
+    crate::{
+        index::SlotIndex,
+        node::{Epoch, NodeError, NodeReader, Nodes, RawNode},
+        schedule::EpochSchedule,
+        source::{CarSource, FileSource},
+        util, varint,
+    },
+    cid::Cid,
+    std::{
+        collections::BTreeMap,
+        io,
+        path::{Path, PathBuf},
+    },
+    thiserror::Error,
+    tokio::{
+        fs::{self, File},
+        io::BufReader,
+    },
+};
+#[cfg(feature = "solana")]
+use {
+    crate::{
+        index::{IndexedTransaction, SignatureIndex},
+        solana::DecodeError,
+    },
+    solana_sdk::signature::Signature,
+};
+
+/// Number of bytes at the end of the file where the root `Epoch` node is searched
+const ROOT_TAIL_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug)]
+pub struct EpochFile {
+    pub path: PathBuf,
+    pub root: Cid,
+    pub node: Epoch,
+    pub slots: Option<SlotIndex>,
+    #[cfg(feature = "solana")]
+    pub signatures: Option<SignatureIndex>,
+}
+
+impl EpochFile {
+    /// Read the header and the root `Epoch` node, the rest of the file is not read
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, CatalogError> {
+        let path = path.into();
+        let mut reader = NodeReader::new(BufReader::new(File::open(&path).await?));
+        let root = get_root(reader.read_header().await?)?
+            .ok_or_else(|| CatalogError::MissedRoot(path.clone()))?;
+        let node = read_root_node(&path, &root).await?;
+        Ok(Self {
+            path,
+            root,
+            node,
+            slots: None,
+            #[cfg(feature = "solana")]
+            signatures: None,
+        })
+    }
+
+    pub const fn epoch(&self) -> u64 {
+        self.node.epoch
+    }
+
+    /// Scan the whole file and build slot index (and signature index with solana feature),
+    /// every block should be in the slot range of the epoch
+    pub async fn build_index(&mut self, schedule: &EpochSchedule) -> Result<(), CatalogError> {
+        let reader = BufReader::new(File::open(&self.path).await?);
+        let epoch = self.epoch();
+        let range = schedule.get_slot_range(epoch);
+        let check_range = |slot| {
+            if range.contains(&slot) {
+                Ok(())
+            } else {
+                Err(CatalogError::SlotOutOfRange { epoch, slot })
+            }
+        };
+        #[cfg(feature = "solana")]
+        {
+            let mut signatures = SignatureIndex::new(false);
+            let slots = SlotIndex::build(reader, |block, nodes| {
+                check_range(block.slot)?;
+                signatures.push_block(nodes).map_err(CatalogError::from)
+            })
+            .await?;
+            self.slots = Some(slots);
+            self.signatures = Some(signatures);
+        }
+        #[cfg(not(feature = "solana"))]
+        {
+            let slots = SlotIndex::build(reader, |block, _nodes| check_range(block.slot)).await?;
+            self.slots = Some(slots);
+        }
+        Ok(())
+    }
+}
+
+/// Epoch files in a directory, named as `epoch-N.car`
+#[derive(Debug, Default)]
+pub struct EpochCatalog {
+    pub schedule: EpochSchedule,
+    pub epochs: BTreeMap<u64, EpochFile>,
+}
+
+impl EpochCatalog {
+    /// Open catalog of mainnet-beta epochs
+    pub async fn open(dir: impl AsRef<Path>) -> Result<Self, CatalogError> {
+        Self::open_with_schedule(dir, EpochSchedule::default()).await
+    }
+
+    pub async fn open_with_schedule(
+        dir: impl AsRef<Path>,
+        schedule: EpochSchedule,
+    ) -> Result<Self, CatalogError> {
+        let mut catalog = Self {
+            schedule,
+            epochs: BTreeMap::new(),
+        };
+        let mut entries = fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let Some(epoch) = entry.file_name().to_str().and_then(parse_file_name) else {
+                continue;
+            };
+
+            let file = EpochFile::open(entry.path()).await?;
+            if file.epoch() != epoch {
+                return Err(CatalogError::EpochMismatch {
+                    epoch: file.epoch(),
+                    path: file.path,
+                });
+            }
+            catalog.epochs.insert(epoch, file);
+        }
+        Ok(catalog)
+    }
+
+    pub fn get(&self, epoch: u64) -> Option<&EpochFile> {
+        self.epochs.get(&epoch)
+    }
+
+    pub fn get_by_slot(&self, slot: u64) -> Option<&EpochFile> {
+        self.get(self.schedule.get_epoch(slot))
+    }
+
+    pub async fn build_index(&mut self, epoch: u64) -> Result<(), CatalogError> {
+        self.epochs
+            .get_mut(&epoch)
+            .ok_or(CatalogError::UnknownEpoch(epoch))?
+            .build_index(&self.schedule)
+            .await
+    }
+
+    /// Read block from the epoch file of the slot, the epoch should be indexed before
+    pub async fn read_block(&self, slot: u64) -> Result<Option<Nodes>, CatalogError> {
+        let epoch = self.schedule.get_epoch(slot);
+        let Some(file) = self.get(epoch) else {
+            return Ok(None);
+        };
+        let slots = file.slots.as_ref().ok_or(CatalogError::NotIndexed(epoch))?;
+        slots
+            .fetch_block(&FileSource::new(&file.path), slot)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Search transaction in indexed epochs, newest epoch first
+    #[cfg(feature = "solana")]
+    pub fn get_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Option<(&EpochFile, &IndexedTransaction)> {
+        self.epochs.values().rev().find_map(|file| {
+            file.signatures
+                .as_ref()
+                .and_then(|signatures| signatures.get(signature))
+                .map(|tx| (file, tx))
+        })
+    }
+}
+
+fn parse_file_name(name: &str) -> Option<u64> {
+    name.strip_prefix("epoch-")?
+        .strip_suffix(".car")?
+        .parse()
+        .ok()
+}
+
+/// First root from CARv1 header
+fn get_root(header: &[u8]) -> Result<Option<Cid>, NodeError> {
+    let serde_cbor::Value::Map(mut header) = serde_cbor::from_slice(header)? else {
+        return Err(NodeError::UnexpectedCborValue {
+            path: "Header",
+            kind: "Map",
+        });
+    };
+    let Some(roots) = header.remove(&serde_cbor::Value::Text("roots".to_owned())) else {
+        return Ok(None);
+    };
+    util::cbor::get_array(roots, "Header::roots")?
+        .into_iter()
+        .next()
+        .map(|root| util::cbor::get_cid(root, "Header::roots"))
+        .transpose()
+}
+
+/// Root `Epoch` node is the last section of the file, find it by the CID in the tail
+async fn read_root_node(path: &Path, root: &Cid) -> Result<Epoch, CatalogError> {
+    let len = fs::metadata(path).await?.len();
+    let tail_len = len.min(ROOT_TAIL_SIZE);
+    let tail = FileSource::new(path)
+        .read_at(len - tail_len, tail_len)
+        .await?;
+
+    let cid = root.to_bytes();
+    for position in (1..tail.len()).filter(|&position| tail[position..].starts_with(&cid)) {
+        // length prefix right before the CID should cover the rest of the file
+        for prefix in 1..=position.min(10) {
+            let mut buf = &tail[position - prefix..position];
+            match varint::decode_varint(&mut buf) {
+                Ok(length)
+                    if buf.is_empty()
+                        && (position as u64).checked_add(length) == Some(tail_len) =>
+                {
+                    let node = RawNode::new_from_vec(tail[position..].to_vec())?;
+                    return Epoch::try_from(node.get_data()).map_err(Into::into);
+                }
+                _ => {}
+            }
+        }
+    }
+    Err(CatalogError::RootNotFound(path.to_owned()))
+}
+
+#[derive(Debug, Error)]
+pub enum CatalogError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Node(#[from] NodeError),
+    #[cfg(feature = "solana")]
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error("no roots in the header: {0:?}")]
+    MissedRoot(PathBuf),
+    #[error("root node is not the last section: {0:?}")]
+    RootNotFound(PathBuf),
+    #[error("file {path:?} contains epoch {epoch}")]
+    EpochMismatch { path: PathBuf, epoch: u64 },
+    #[error("unknown epoch: {0}")]
+    UnknownEpoch(u64),
+    #[error("epoch {0} is not indexed")]
+    NotIndexed(u64),
+    #[error("slot {slot} is out of epoch {epoch}")]
+    SlotOutOfRange { epoch: u64, slot: u64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            catalog::{CatalogError, EpochCatalog},
+            node::Node,
+            schedule::{DEFAULT_SLOTS_PER_EPOCH as SLOTS_PER_EPOCH, EpochSchedule},
+            util::tests::{
+                TempDir, decode_hex, encode_block, encode_car, encode_cid, encode_epoch,
+            },
+        },
+        std::{fs, num::NonZeroU64},
+    };
+
+    #[tokio::test]
+    async fn test_catalog() {
+        let temp = TempDir::new("car-catalog");
+        let dir = &temp.0;
+        let entry = decode_hex(
+            "84011930d458203a43cd82e140873740fde924da4125ac30e2fec5eb92344dbb2bb4776973feec80",
+        );
+        let entry_cid = encode_cid(&entry);
+        for epoch in [0, 1] {
+            let block = encode_block(epoch * SLOTS_PER_EPOCH + 1, &[entry_cid]);
+            let root = encode_epoch(epoch, &[]);
+            let car = encode_car(&[
+                (entry_cid, entry.clone()),
+                (encode_cid(&block), block),
+                (encode_cid(&root), root),
+            ]);
+            fs::write(dir.join(format!("epoch-{epoch}.car")), car).unwrap();
+        }
+        fs::write(dir.join("epoch-2.car.tmp"), b"ignored").unwrap();
+
+        let mut catalog = EpochCatalog::open(dir).await.expect("valid catalog");
+        assert_eq!(catalog.epochs.keys().copied().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(catalog.get_by_slot(SLOTS_PER_EPOCH + 5).unwrap().epoch(), 1);
+        assert!(catalog.get_by_slot(2 * SLOTS_PER_EPOCH).is_none());
+
+        assert!(matches!(
+            catalog.read_block(1).await,
+            Err(CatalogError::NotIndexed(0))
+        ));
+        catalog.build_index(1).await.expect("valid epoch");
+        let nodes = catalog
+            .read_block(SLOTS_PER_EPOCH + 1)
+            .await
+            .expect("valid block")
+            .expect("block exists");
+        assert!(matches!(
+            nodes.nodes.values().last(),
+            Some(Node::Block(block)) if block.slot == SLOTS_PER_EPOCH + 1
+        ));
+
+        let mut catalog = EpochCatalog::open_with_schedule(
+            dir,
+            EpochSchedule::new(NonZeroU64::new(8192).unwrap(), true),
+        )
+        .await
+        .expect("valid catalog");
+        assert!(matches!(
+            catalog.build_index(1).await,
+            Err(CatalogError::SlotOutOfRange { epoch: 1, slot }) if slot == SLOTS_PER_EPOCH + 1
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+use {
+    crate::node::{NodeReader, NodeReaderOptions},
+    async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder},
+    std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncSeek, ReadBuf},
+};
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    None,
+    Zstd,
+    Gzip,
+}
+
+impl Compression {
+    /// Detect compression from the first bytes of the input
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&ZSTD_MAGIC) {
+            Self::Zstd
+        } else if magic.starts_with(&GZIP_MAGIC) {
+            Self::Gzip
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Plain, zstd or gzip compressed input, concatenated frames/members are supported
+#[derive(Debug)]
+pub enum DecompressReader<R> {
+    None(R),
+    Zstd(ZstdDecoder<R>),
+    Gzip(GzipDecoder<R>),
+}
+
+impl<R: AsyncBufRead + Unpin> DecompressReader<R> {
+    pub fn new(reader: R, compression: Compression) -> Self {
+        match compression {
+            Compression::None => Self::None(reader),
+            Compression::Zstd => {
+                let mut decoder = ZstdDecoder::new(reader);
+                decoder.multiple_members(true);
+                Self::Zstd(decoder)
+            }
+            Compression::Gzip => {
+                let mut decoder = GzipDecoder::new(reader);
+                decoder.multiple_members(true);
+                Self::Gzip(decoder)
+            }
+        }
+    }
+
+    /// Detect compression from magic bytes without consuming them
+    pub async fn detect(mut reader: R) -> io::Result<Self> {
+        let compression = Compression::detect(reader.fill_buf().await?);
+        Ok(Self::new(reader, compression))
+    }
+
+    pub const fn compression(&self) -> Compression {
+        match self {
+            Self::None(_) => Compression::None,
+            Self::Zstd(_) => Compression::Zstd,
+            Self::Gzip(_) => Compression::Gzip,
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for DecompressReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::None(reader) => Pin::new(reader).poll_read(cx, buf),
+            Self::Zstd(reader) => Pin::new(reader).poll_read(cx, buf),
+            Self::Gzip(reader) => Pin::new(reader).poll_read(cx, buf),
+        }
+    }
+}
+
+/// Only plain input can be seeked, compressed input returns `ErrorKind::Unsupported`
+impl<R: AsyncBufRead + AsyncSeek + Unpin> AsyncSeek for DecompressReader<R> {
+    fn start_seek(self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        match self.get_mut() {
+            Self::None(reader) => Pin::new(reader).start_seek(position),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "seek is not supported for compressed input",
+            )),
+        }
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        match self.get_mut() {
+            Self::None(reader) => Pin::new(reader).poll_complete(cx),
+            _ => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "seek is not supported for compressed input",
+            ))),
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> NodeReader<DecompressReader<R>> {
+    pub fn zstd(reader: R) -> Self {
+        Self::new(DecompressReader::new(reader, Compression::Zstd))
+    }
+
+    pub fn gzip(reader: R) -> Self {
+        Self::new(DecompressReader::new(reader, Compression::Gzip))
+    }
+
+    /// Create reader with compression detected from magic bytes
+    pub async fn detect(reader: R, options: NodeReaderOptions) -> io::Result<Self> {
+        DecompressReader::detect(reader)
+            .await
+            .map(|reader| options.build(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            compression::Compression,
+            node::{NodeReader, NodeReaderOptions},
+            util::tests::{decode_hex, encode_car, encode_cid},
+        },
+        async_compression::tokio::bufread::{GzipEncoder, ZstdEncoder},
+        tokio::io::AsyncReadExt,
+    };
+
+    #[tokio::test]
+    async fn test_detect() {
+        let data = decode_hex(
+            "84011930d458203a43cd82e140873740fde924da4125ac30e2fec5eb92344dbb2bb4776973feec80",
+        );
+        let cid = encode_cid(&data);
+        let car = encode_car(&[(cid, data)]);
+
+        let mut zstd = vec![];
+        ZstdEncoder::new(car.as_slice())
+            .read_to_end(&mut zstd)
+            .await
+            .unwrap();
+        let mut gzip = vec![];
+        GzipEncoder::new(car.as_slice())
+            .read_to_end(&mut gzip)
+            .await
+            .unwrap();
+
+        for (input, compression) in [
+            (car.as_slice(), Compression::None),
+            (zstd.as_slice(), Compression::Zstd),
+            (gzip.as_slice(), Compression::Gzip),
+        ] {
+            let mut reader = NodeReader::detect(input, NodeReaderOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(reader.get_ref().compression(), compression);
+            assert_eq!(reader.read_node().await.unwrap().unwrap().cid, cid);
+            assert!(reader.read_node().await.unwrap().is_none());
+        }
+    }
+}
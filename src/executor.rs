@@ -0,0 +1,311 @@
+use {
+    crate::replay::{ExecutionResult, ReplayExecutor},
+    solana_runtime::{
+        accounts_background_service::AbsRequestSender,
+        bank::Bank,
+        bank_forks::{BankForks, SetRootError},
+        installed_scheduler_pool::BankWithScheduler,
+    },
+    solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
+    solana_sdk::{
+        clock::MAX_PROCESSING_AGE,
+        hash::Hash,
+        message::{SanitizedMessage, SimpleAddressLoader, v0::LoadedAddresses},
+        transaction::{MessageHash, SanitizedTransaction, TransactionError},
+    },
+    solana_svm::transaction_processor::ExecutionRecordingConfig,
+    solana_timings::ExecuteTimings,
+    std::{
+        fmt, slice,
+        sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    },
+    thiserror::Error,
+};
+
+/// Executes blocks with the runtime on child banks of `bank`, e.g. a bank restored from the
+/// snapshot of the parent slot; transactions are loaded, executed and committed one by one in
+/// the block order, so conflicting transactions of a block see each other's updates
+pub struct BankExecutor {
+    bank_forks: Arc<RwLock<BankForks>>,
+    bank: Arc<Bank>,
+}
+
+impl fmt::Debug for BankExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BankExecutor")
+            .field("slot", &self.bank.slot())
+            .finish()
+    }
+}
+
+impl BankExecutor {
+    pub fn new(bank: Bank) -> Self {
+        let bank_forks = BankForks::new_rw_arc(bank);
+        let bank = read(&bank_forks).root_bank();
+        Self { bank_forks, bank }
+    }
+
+    /// Bank of the last executed block, the parent bank if no block was executed yet
+    pub const fn bank(&self) -> &Arc<Bank> {
+        &self.bank
+    }
+
+    /// Register ticks left in the slot of the last executed block, freeze the bank and make it
+    /// the root; `blockhash` is the hash of the last entry and is registered with the last
+    /// tick, so transactions of the next blocks can refer to it
+    pub fn finish_block(&mut self, blockhash: &Hash) -> Result<(), ExecutorError> {
+        let scheduler = BankWithScheduler::no_scheduler_available();
+        while self.bank.tick_height() < self.bank.max_tick_height() {
+            let hash = if self.bank.tick_height() + 1 == self.bank.max_tick_height() {
+                *blockhash
+            } else {
+                Hash::default()
+            };
+            self.bank.register_tick(&hash, &scheduler);
+        }
+        self.bank.freeze();
+        write(&self.bank_forks)
+            .set_root(self.bank.slot(), &AbsRequestSender::default(), None)
+            .map_err(|error| ExecutorError::SetRoot(Box::new(error)))?;
+        Ok(())
+    }
+
+    fn execute(
+        bank: &Bank,
+        index: usize,
+        transaction: &SanitizedTransaction,
+    ) -> Result<ExecutionResult, ExecutorError> {
+        let transaction = runtime_transaction(bank, transaction)
+            .map_err(|error| ExecutorError::Sanitize { index, error })?;
+        let batch = bank.prepare_sanitized_batch(slice::from_ref(&transaction));
+        let (mut results, balances) = bank.load_execute_and_commit_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            true,
+            ExecutionRecordingConfig {
+                enable_cpi_recording: false,
+                enable_log_recording: true,
+                enable_return_data_recording: false,
+            },
+            &mut ExecuteTimings::default(),
+            None,
+        );
+        let post_balances = balances
+            .post_balances
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        Ok(match results.pop() {
+            Some(Ok(committed)) => ExecutionResult {
+                fee: committed.fee_details.total_fee(),
+                status: committed.status,
+                post_balances,
+                log_messages: committed.log_messages,
+            },
+            // not committed, e.g. the fee payer can not pay the fee
+            Some(Err(error)) => ExecutionResult {
+                fee: 0,
+                status: Err(error),
+                post_balances,
+                log_messages: None,
+            },
+            None => return Err(ExecutorError::MissedResult { index }),
+        })
+    }
+}
+
+impl ReplayExecutor for BankExecutor {
+    type Error = ExecutorError;
+
+    fn execute_block(
+        &mut self,
+        slot: u64,
+        transactions: &[SanitizedTransaction],
+    ) -> Result<Vec<ExecutionResult>, Self::Error> {
+        let parent = self.bank.slot();
+        if slot <= parent {
+            return Err(ExecutorError::InvalidSlot { slot, parent });
+        }
+
+        let collector_id = *self.bank.collector_id();
+        let bank = Bank::new_from_parent(Arc::clone(&self.bank), &collector_id, slot);
+        self.bank = write(&self.bank_forks)
+            .insert(bank)
+            .clone_without_scheduler();
+        transactions
+            .iter()
+            .enumerate()
+            .map(|(index, transaction)| Self::execute(&self.bank, index, transaction))
+            .collect()
+    }
+}
+
+/// Runtime transaction with the addresses loaded when `transaction` was sanitized
+fn runtime_transaction(
+    bank: &Bank,
+    transaction: &SanitizedTransaction,
+) -> Result<RuntimeTransaction<SanitizedTransaction>, TransactionError> {
+    let loaded_addresses = match transaction.message() {
+        SanitizedMessage::Legacy(_) => LoadedAddresses::default(),
+        SanitizedMessage::V0(message) => message.loaded_addresses.clone().into_owned(),
+    };
+    RuntimeTransaction::try_create(
+        transaction.to_versioned_transaction(),
+        MessageHash::Precomputed(*transaction.message_hash()),
+        Some(transaction.is_simple_vote_transaction()),
+        SimpleAddressLoader::Enabled(loaded_addresses),
+        bank.get_reserved_account_keys(),
+    )
+}
+
+// banks are replaced as a whole, so a panic while the lock is held can not leave them broken
+fn read(bank_forks: &RwLock<BankForks>) -> RwLockReadGuard<'_, BankForks> {
+    bank_forks.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write(bank_forks: &RwLock<BankForks>) -> RwLockWriteGuard<'_, BankForks> {
+    bank_forks.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Debug, Error)]
+pub enum ExecutorError {
+    #[error("slot {slot} is not after the parent slot {parent}")]
+    InvalidSlot { slot: u64, parent: u64 },
+    #[error("failed to sanitize transaction {index}: {error}")]
+    Sanitize {
+        index: usize,
+        error: TransactionError,
+    },
+    #[error("transaction {index} has no commit result")]
+    MissedResult { index: usize },
+    #[error("failed to set root: {0}")]
+    SetRoot(Box<SetRootError>),
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            executor::{BankExecutor, ExecutorError},
+            replay::{ReplayExecutor, ReplayTransaction, replay_block},
+            util::tests::TempDir,
+        },
+        solana_runtime::{
+            bank::Bank,
+            genesis_utils::{
+                bootstrap_validator_stake_lamports, create_genesis_config_with_leader,
+            },
+        },
+        solana_sdk::{
+            fee_calculator::FeeRateGovernor,
+            hash::Hash,
+            instruction::InstructionError,
+            native_token::LAMPORTS_PER_SOL,
+            pubkey::Pubkey,
+            system_transaction,
+            transaction::{SanitizedTransaction, TransactionError},
+        },
+        solana_transaction_status::TransactionStatusMeta,
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn test_bank_executor() {
+        let temp = TempDir::new("car-executor");
+        let mut genesis = create_genesis_config_with_leader(
+            10 * LAMPORTS_PER_SOL,
+            &Pubkey::new_unique(),
+            bootstrap_validator_stake_lamports(),
+        );
+        genesis.genesis_config.fee_rate_governor = FeeRateGovernor::new(5000, 0);
+        let bank = Bank::new_with_paths(
+            &genesis.genesis_config,
+            Arc::default(),
+            vec![temp.0.clone()],
+            None,
+            None,
+            false,
+            None,
+            None,
+            None,
+            Arc::default(),
+            None,
+            None,
+        );
+        let mut executor = BankExecutor::new(bank);
+
+        // the second transfer fails, but the fee is charged
+        let payer = genesis.mint_keypair;
+        let to = Pubkey::new_unique();
+        let transfer = |lamports, blockhash| {
+            SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+                &payer, &to, lamports, blockhash,
+            ))
+        };
+        let blockhash = executor.bank().last_blockhash();
+        let results = executor
+            .execute_block(
+                1,
+                &[
+                    transfer(6 * LAMPORTS_PER_SOL, blockhash),
+                    transfer(6 * LAMPORTS_PER_SOL + 1, blockhash),
+                ],
+            )
+            .unwrap();
+        assert_eq!(results[0].fee, 5000);
+        assert_eq!(results[0].status, Ok(()));
+        assert_eq!(
+            results[0].post_balances,
+            [4 * LAMPORTS_PER_SOL - 5000, 6 * LAMPORTS_PER_SOL, 1]
+        );
+        assert_eq!(
+            results[0].log_messages.as_deref(),
+            Some(
+                [
+                    "Program 11111111111111111111111111111111 invoke [1]".to_owned(),
+                    "Program 11111111111111111111111111111111 success".to_owned(),
+                ]
+                .as_slice()
+            )
+        );
+        assert_eq!(results[1].fee, 5000);
+        assert_eq!(
+            results[1].status,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            ))
+        );
+        assert_eq!(
+            results[1].post_balances,
+            [4 * LAMPORTS_PER_SOL - 10000, 6 * LAMPORTS_PER_SOL, 1]
+        );
+
+        // transactions of the next block refer to the blockhash of the finished one
+        let blockhash = Hash::new_unique();
+        executor.finish_block(&blockhash).unwrap();
+        assert!(executor.bank().is_frozen());
+        let transaction = transfer(LAMPORTS_PER_SOL, blockhash);
+        let expected = results[0].clone();
+        let mismatches = replay_block(
+            &mut executor,
+            2,
+            &[ReplayTransaction {
+                transaction,
+                meta: TransactionStatusMeta {
+                    status: expected.status,
+                    fee: expected.fee,
+                    post_balances: vec![3 * LAMPORTS_PER_SOL - 15000, 7 * LAMPORTS_PER_SOL, 1],
+                    log_messages: expected.log_messages,
+                    ..Default::default()
+                },
+            }],
+        )
+        .unwrap();
+        assert!(mismatches.is_empty(), "{mismatches:?}");
+        assert!(matches!(
+            executor.execute_block(2, &[]),
+            Err(ExecutorError::InvalidSlot { slot: 2, parent: 2 })
+        ));
+    }
+}
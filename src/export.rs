@@ -0,0 +1,466 @@
+use {
+    crate::{
+        node::Nodes,
+        solana::{BlockhashTracker, DecodeError},
+    },
+    arrow::{
+        array::{
+            ArrayRef, Int64Array, ListBuilder, RecordBatch, StringArray, StringBuilder, UInt8Array,
+            UInt64Array,
+        },
+        datatypes::{DataType, Field, Schema, SchemaRef},
+        error::ArrowError,
+    },
+    parquet::{
+        arrow::ArrowWriter,
+        basic::{Compression, ZstdLevel},
+        errors::ParquetError,
+        file::properties::WriterProperties,
+    },
+    solana_sdk::signature::Signature,
+    solana_transaction_status::TransactionWithStatusMeta,
+    std::{
+        fs::File,
+        io::{self, Write},
+        num::NonZeroUsize,
+        path::Path,
+        sync::Arc,
+    },
+    thiserror::Error,
+};
+
+pub const DEFAULT_ROW_GROUP_SIZE: NonZeroUsize = NonZeroUsize::new(64 * 1024).unwrap();
+
+/// Writes blocks, transactions and rewards of the CAR file into three Parquet tables
+#[derive(Debug)]
+pub struct ParquetExporter<W: Write + Send> {
+    blocks: TableWriter<BlockRow, W>,
+    transactions: TableWriter<TransactionRow, W>,
+    rewards: TableWriter<RewardRow, W>,
+    blockhashes: BlockhashTracker,
+}
+
+impl ParquetExporter<File> {
+    /// Create `blocks.parquet`, `transactions.parquet` and `rewards.parquet` in the directory
+    pub fn create(
+        dir: impl AsRef<Path>,
+        row_group_size: NonZeroUsize,
+    ) -> Result<Self, ExportError> {
+        let dir = dir.as_ref();
+        Self::new(
+            File::create(dir.join("blocks.parquet"))?,
+            File::create(dir.join("transactions.parquet"))?,
+            File::create(dir.join("rewards.parquet"))?,
+            row_group_size,
+        )
+    }
+}
+
+impl<W: Write + Send> ParquetExporter<W> {
+    pub fn new(
+        blocks: W,
+        transactions: W,
+        rewards: W,
+        row_group_size: NonZeroUsize,
+    ) -> Result<Self, ExportError> {
+        Ok(Self {
+            blocks: TableWriter::new(blocks, row_group_size)?,
+            transactions: TableWriter::new(transactions, row_group_size)?,
+            rewards: TableWriter::new(rewards, row_group_size)?,
+            blockhashes: BlockhashTracker::new(),
+        })
+    }
+
+    /// Push all nodes of a single block, as read by `Nodes::read_until_block`
+    pub fn push_block(&mut self, nodes: &Nodes) -> Result<(), ExportError> {
+        let block = nodes.get_block().ok_or(DecodeError::MissedBlock)?;
+        let hashes = self.blockhashes.push(nodes)?;
+
+        let transactions = nodes.get_block_transactions(block)?;
+        for (index, transaction) in transactions.iter().enumerate() {
+            let tx = match nodes.decode_transaction(transaction)? {
+                TransactionWithStatusMeta::MissingMetadata(tx) => TransactionRow {
+                    slot: block.slot,
+                    index: transaction.index.unwrap_or(index as u64),
+                    signature: get_signature(&tx.signatures),
+                    fee: None,
+                    err: None,
+                    compute_units_consumed: None,
+                    accounts: tx
+                        .message
+                        .account_keys
+                        .iter()
+                        .map(|key| key.to_string())
+                        .collect(),
+                },
+                TransactionWithStatusMeta::Complete(tx) => TransactionRow {
+                    slot: block.slot,
+                    index: transaction.index.unwrap_or(index as u64),
+                    signature: get_signature(&tx.transaction.signatures),
+                    fee: Some(tx.meta.fee),
+                    err: tx
+                        .meta
+                        .status
+                        .as_ref()
+                        .err()
+                        .map(serde_json::to_string)
+                        .transpose()?,
+                    compute_units_consumed: tx.meta.compute_units_consumed,
+                    accounts: tx
+                        .account_keys()
+                        .iter()
+                        .map(|key| key.to_string())
+                        .collect(),
+                },
+            };
+            self.transactions.push(tx)?;
+        }
+
+        if let Some(rewards) = nodes.get_rewards(&block.rewards)? {
+            let (rewards, _num_partitions) = nodes.decode_rewards(rewards)?;
+            for reward in rewards {
+                self.rewards.push(RewardRow {
+                    slot: block.slot,
+                    pubkey: reward.pubkey,
+                    lamports: reward.lamports,
+                    post_balance: reward.post_balance,
+                    reward_type: reward
+                        .reward_type
+                        .map(|reward_type| reward_type.to_string()),
+                    commission: reward.commission,
+                })?;
+            }
+        }
+
+        self.blocks.push(BlockRow {
+            slot: block.slot,
+            parent_slot: block.meta.parent_slot,
+            blocktime: (block.meta.blocktime != 0).then_some(block.meta.blocktime as i64),
+            block_height: block.meta.block_height,
+            blockhash: hashes.blockhash.to_string(),
+            previous_blockhash: hashes.previous_blockhash.map(|hash| hash.to_string()),
+            transaction_count: transactions.len() as u64,
+        })
+    }
+
+    /// Flush buffered rows and write Parquet footers
+    pub fn finish(self) -> Result<(), ExportError> {
+        self.blocks.finish()?;
+        self.transactions.finish()?;
+        self.rewards.finish()
+    }
+}
+
+fn get_signature(signatures: &[Signature]) -> String {
+    signatures
+        .first()
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+trait Row: Sized {
+    fn schema() -> SchemaRef;
+
+    fn columns(rows: &[Self]) -> Vec<ArrayRef>;
+}
+
+#[derive(Debug)]
+struct TableWriter<T, W: Write + Send> {
+    writer: ArrowWriter<W>,
+    rows: Vec<T>,
+    row_group_size: NonZeroUsize,
+}
+
+impl<T: Row, W: Write + Send> TableWriter<T, W> {
+    fn new(writer: W, row_group_size: NonZeroUsize) -> Result<Self, ExportError> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_max_row_group_size(row_group_size.get())
+            .build();
+        Ok(Self {
+            writer: ArrowWriter::try_new(writer, T::schema(), Some(props))?,
+            rows: Vec::with_capacity(row_group_size.get()),
+            row_group_size,
+        })
+    }
+
+    fn push(&mut self, row: T) -> Result<(), ExportError> {
+        self.rows.push(row);
+        if self.rows.len() >= self.row_group_size.get() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    // every flush produce a row group, so memory is bounded by `row_group_size` rows
+    fn flush(&mut self) -> Result<(), ExportError> {
+        if !self.rows.is_empty() {
+            let batch = RecordBatch::try_new(T::schema(), T::columns(&self.rows))?;
+            self.writer.write(&batch)?;
+            self.writer.flush()?;
+            self.rows.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), ExportError> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct BlockRow {
+    slot: u64,
+    parent_slot: u64,
+    blocktime: Option<i64>,
+    block_height: Option<u64>,
+    blockhash: String,
+    previous_blockhash: Option<String>,
+    transaction_count: u64,
+}
+
+impl Row for BlockRow {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("slot", DataType::UInt64, false),
+            Field::new("parent_slot", DataType::UInt64, false),
+            Field::new("blocktime", DataType::Int64, true),
+            Field::new("block_height", DataType::UInt64, true),
+            Field::new("blockhash", DataType::Utf8, false),
+            Field::new("previous_blockhash", DataType::Utf8, true),
+            Field::new("transaction_count", DataType::UInt64, false),
+        ]))
+    }
+
+    fn columns(rows: &[Self]) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.slot),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.parent_slot),
+            )),
+            Arc::new(Int64Array::from_iter(rows.iter().map(|row| row.blocktime))),
+            Arc::new(UInt64Array::from_iter(
+                rows.iter().map(|row| row.block_height),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|row| &row.blockhash),
+            )),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|row| row.previous_blockhash.as_deref()),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.transaction_count),
+            )),
+        ]
+    }
+}
+
+#[derive(Debug)]
+struct TransactionRow {
+    slot: u64,
+    index: u64,
+    signature: String,
+    fee: Option<u64>,
+    err: Option<String>,
+    compute_units_consumed: Option<u64>,
+    accounts: Vec<String>,
+}
+
+impl Row for TransactionRow {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("slot", DataType::UInt64, false),
+            Field::new("index", DataType::UInt64, false),
+            Field::new("signature", DataType::Utf8, false),
+            Field::new("fee", DataType::UInt64, true),
+            Field::new("err", DataType::Utf8, true),
+            Field::new("compute_units_consumed", DataType::UInt64, true),
+            Field::new(
+                "accounts",
+                DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))),
+                false,
+            ),
+        ]))
+    }
+
+    fn columns(rows: &[Self]) -> Vec<ArrayRef> {
+        let mut accounts = ListBuilder::new(StringBuilder::new());
+        for row in rows {
+            accounts.append_value(row.accounts.iter().map(Some));
+        }
+
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.slot),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.index),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|row| &row.signature),
+            )),
+            Arc::new(UInt64Array::from_iter(rows.iter().map(|row| row.fee))),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|row| row.err.as_ref()),
+            )),
+            Arc::new(UInt64Array::from_iter(
+                rows.iter().map(|row| row.compute_units_consumed),
+            )),
+            Arc::new(accounts.finish()),
+        ]
+    }
+}
+
+#[derive(Debug)]
+struct RewardRow {
+    slot: u64,
+    pubkey: String,
+    lamports: i64,
+    post_balance: u64,
+    reward_type: Option<String>,
+    commission: Option<u8>,
+}
+
+impl Row for RewardRow {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("slot", DataType::UInt64, false),
+            Field::new("pubkey", DataType::Utf8, false),
+            Field::new("lamports", DataType::Int64, false),
+            Field::new("post_balance", DataType::UInt64, false),
+            Field::new("reward_type", DataType::Utf8, true),
+            Field::new("commission", DataType::UInt8, true),
+        ]))
+    }
+
+    fn columns(rows: &[Self]) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.slot),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|row| &row.pubkey),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                rows.iter().map(|row| row.lamports),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.post_balance),
+            )),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|row| row.reward_type.as_ref()),
+            )),
+            Arc::new(UInt8Array::from_iter(rows.iter().map(|row| row.commission))),
+        ]
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{export::ParquetExporter, solana::tests::create_nodes},
+        arrow::{
+            array::{AsArray, RecordBatch},
+            compute::concat_batches,
+            datatypes::UInt64Type,
+        },
+        bytes::Bytes,
+        parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+        std::{num::NonZeroUsize, sync::Arc},
+    };
+
+    // returns number of row groups and all rows in one batch
+    fn read_table(data: Vec<u8>) -> (usize, RecordBatch) {
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data)).expect("valid parquet");
+        let row_groups = builder.metadata().num_row_groups();
+        let schema = Arc::clone(builder.schema());
+        let batches = builder
+            .build()
+            .expect("valid reader")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("valid batches");
+        let batch = concat_batches(&schema, &batches).expect("valid batches");
+        (row_groups, batch)
+    }
+
+    #[test]
+    fn test_export() {
+        let nodes = create_nodes();
+
+        let mut blocks = vec![];
+        let mut transactions = vec![];
+        let mut rewards = vec![];
+        let mut exporter = ParquetExporter::new(
+            &mut blocks,
+            &mut transactions,
+            &mut rewards,
+            NonZeroUsize::MIN,
+        )
+        .unwrap();
+        exporter.push_block(&nodes).expect("valid block");
+        exporter.push_block(&nodes).expect("valid block");
+        exporter.finish().expect("valid export");
+
+        let (row_groups, blocks) = read_table(blocks);
+        assert_eq!(row_groups, 2);
+        assert_eq!(
+            blocks
+                .column_by_name("transaction_count")
+                .unwrap()
+                .as_primitive::<UInt64Type>()
+                .values(),
+            &[1, 1]
+        );
+
+        let (row_groups, transactions) = read_table(transactions);
+        assert_eq!(row_groups, 2);
+        assert_eq!(
+            transactions
+                .column_by_name("signature")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "3hLwbGQetenDLR5sq3j1gemN2u8TA12knQTDa8NZoDC4tBrdSX22yEP8noNTEFjPHGSmpQAyqt97V3BXsMX1o3fu"
+        );
+        assert_eq!(
+            transactions
+                .column_by_name("fee")
+                .unwrap()
+                .as_primitive::<UInt64Type>()
+                .value(0),
+            5000
+        );
+        assert_eq!(
+            transactions
+                .column_by_name("accounts")
+                .unwrap()
+                .as_list::<i32>()
+                .value(0)
+                .len(),
+            5
+        );
+
+        let (row_groups, rewards) = read_table(rewards);
+        assert_eq!(row_groups, 0);
+        assert_eq!(rewards.num_rows(), 0);
+    }
+}
@@ -0,0 +1,238 @@
+use {
+    crate::{
+        node::{Nodes, Transaction},
+        solana::{DecodeError, parse_transaction_status_meta},
+    },
+    prost::Message,
+    solana_sdk::pubkey::Pubkey,
+    solana_transaction_status::TransactionWithStatusMeta,
+    std::collections::HashSet,
+};
+
+/// Selects transactions referencing any of the accounts, program IDs can be used as well because
+/// they are always part of the static account keys
+#[derive(Debug, Default, Clone)]
+pub struct AccountFilter {
+    accounts: HashSet<Pubkey>,
+}
+
+impl AccountFilter {
+    pub fn new(accounts: impl IntoIterator<Item = Pubkey>) -> Self {
+        Self {
+            accounts: accounts.into_iter().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.accounts.contains(pubkey)
+    }
+
+    /// Matched transactions of the block in the block order
+    pub fn filter_block<'a>(
+        &'a self,
+        nodes: &'a Nodes,
+    ) -> Result<
+        impl Iterator<Item = Result<(&'a Transaction, TransactionWithStatusMeta), DecodeError>> + 'a,
+        DecodeError,
+    > {
+        let block = nodes.get_block().ok_or(DecodeError::MissedBlock)?;
+        Ok(nodes
+            .get_block_transactions(block)?
+            .into_iter()
+            .filter_map(|transaction| {
+                self.filter_transaction(nodes, transaction)
+                    .map(|tx| tx.map(|tx| (transaction, tx)))
+                    .transpose()
+            }))
+    }
+
+    /// Static account keys are checked first, loaded addresses are decoded from the metadata only
+    /// for transactions with address table lookups, full metadata is decoded only on match
+    pub fn filter_transaction(
+        &self,
+        nodes: &Nodes,
+        transaction: &Transaction,
+    ) -> Result<Option<TransactionWithStatusMeta>, DecodeError> {
+        let tx = nodes.decode_versioned_transaction(transaction)?;
+        if tx
+            .message
+            .static_account_keys()
+            .iter()
+            .any(|pubkey| self.contains(pubkey))
+        {
+            let meta = nodes.decode_transaction_status_meta(transaction)?;
+            return Nodes::build_transaction_with_status_meta(tx, meta).map(Some);
+        }
+
+        if tx
+            .message
+            .address_table_lookups()
+            .is_none_or(|lookups| lookups.is_empty())
+        {
+            return Ok(None);
+        }
+
+        let Some(buffer) = nodes.decompress_transaction_status_meta(transaction)? else {
+            return Ok(None);
+        };
+        // bincode encoded metadata was used before address lookup tables were enabled
+        let Ok(loaded) = LoadedAddresses::decode(buffer.as_slice()) else {
+            return Ok(None);
+        };
+        let matched = loaded
+            .writable
+            .iter()
+            .chain(loaded.readonly.iter())
+            .filter_map(|pubkey| Pubkey::try_from(pubkey.as_slice()).ok())
+            .any(|pubkey| self.contains(&pubkey));
+        if !matched {
+            return Ok(None);
+        }
+
+        let meta = parse_transaction_status_meta(&buffer)?;
+        Nodes::build_transaction_with_status_meta(tx, Some(meta)).map(Some)
+    }
+}
+
+/// Loaded addresses fields of `generated::TransactionStatusMeta`, other fields are skipped
+#[derive(Clone, PartialEq, Message)]
+struct LoadedAddresses {
+    #[prost(bytes = "vec", repeated, tag = "12")]
+    writable: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "13")]
+    readonly: Vec<Vec<u8>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            filter::AccountFilter,
+            node::{Block, DataFrame, Entry, Node, Nodes, SlotMeta, Transaction},
+            solana::tests::create_nodes,
+            util::tests::encode_cid,
+        },
+        prost::Message,
+        solana_sdk::{
+            hash::Hash,
+            message::{
+                MessageHeader, VersionedMessage,
+                v0::{self, MessageAddressTableLookup},
+            },
+            pubkey::Pubkey,
+            signature::Signature,
+            transaction::VersionedTransaction,
+        },
+        solana_storage_proto::convert::generated,
+    };
+
+    #[test]
+    fn test_filter_block() {
+        let nodes = create_nodes();
+        let block = nodes.get_block().unwrap();
+        let transaction = nodes.get_block_transactions(block).unwrap()[0];
+        let tx = nodes.decode_versioned_transaction(transaction).unwrap();
+        let program_id = *tx.message.static_account_keys().last().unwrap();
+
+        let filter = AccountFilter::new([program_id]);
+        let matched = filter
+            .filter_block(&nodes)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].0.slot, 16848004);
+        assert_eq!(matched[0].1.transaction_signature(), &tx.signatures[0]);
+
+        let filter = AccountFilter::new([Pubkey::new_unique()]);
+        assert_eq!(filter.filter_block(&nodes).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_loaded_addresses() {
+        let (writable, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 0,
+                },
+                account_keys: vec![Pubkey::new_unique()],
+                recent_blockhash: Hash::default(),
+                instructions: vec![],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1],
+                }],
+            }),
+        };
+        let meta = generated::TransactionStatusMeta {
+            loaded_writable_addresses: vec![writable.to_bytes().to_vec()],
+            loaded_readonly_addresses: vec![readonly.to_bytes().to_vec()],
+            ..Default::default()
+        };
+
+        let (tx_cid, entry_cid) = (encode_cid(b"transaction"), encode_cid(b"entry"));
+        let mut nodes = Nodes::default();
+        for (cid, node) in [
+            (
+                tx_cid,
+                Node::Transaction(Transaction {
+                    data: DataFrame {
+                        data: bincode::serialize(&tx).unwrap(),
+                        ..Default::default()
+                    },
+                    metadata: DataFrame {
+                        data: zstd::encode_all(meta.encode_to_vec().as_slice(), 0).unwrap(),
+                        ..Default::default()
+                    },
+                    slot: 1,
+                    index: Some(0),
+                }),
+            ),
+            (
+                entry_cid,
+                Node::Entry(Entry {
+                    num_hashes: 1,
+                    hash: vec![0; 32],
+                    transactions: vec![tx_cid],
+                }),
+            ),
+            (
+                encode_cid(b"block"),
+                Node::Block(Block {
+                    slot: 1,
+                    shredding: vec![],
+                    entries: vec![entry_cid],
+                    meta: SlotMeta::default(),
+                    rewards: encode_cid(b"rewards"),
+                }),
+            ),
+        ] {
+            nodes.nodes.insert(cid, node);
+        }
+
+        // accounts are only in the loaded addresses
+        for pubkey in [writable, readonly] {
+            let filter = AccountFilter::new([pubkey]);
+            let matched = filter
+                .filter_block(&nodes)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(matched.len(), 1);
+            let loaded = &matched[0].1.get_status_meta().unwrap().loaded_addresses;
+            assert_eq!(loaded.writable, [writable]);
+            assert_eq!(loaded.readonly, [readonly]);
+        }
+        let filter = AccountFilter::new([Pubkey::new_unique()]);
+        assert_eq!(filter.filter_block(&nodes).unwrap().count(), 0);
+    }
+}
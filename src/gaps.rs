@@ -0,0 +1,122 @@
+use crate::{node::Block, schedule::EpochSchedule};
+
+/// Block with a parent other than the previous block in the archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParentMismatch {
+    pub slot: u64,
+    pub parent_slot: u64,
+    /// Slot of the previous block in the archive, `None` for the first block
+    pub previous_slot: Option<u64>,
+}
+
+/// Skipped slots and parent checks over blocks in the archive order
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GapReport {
+    /// First slot expected in the archive
+    pub first_slot: u64,
+    /// Slot of the last pushed block
+    pub last_slot: Option<u64>,
+    pub blocks: u64,
+    /// Slots without block produced by the leader
+    pub skipped: Vec<u64>,
+    /// Parents that are not the previous block, these blocks are missed in the archive
+    pub mismatches: Vec<ParentMismatch>,
+}
+
+impl GapReport {
+    pub const fn new(first_slot: u64) -> Self {
+        Self {
+            first_slot,
+            last_slot: None,
+            blocks: 0,
+            skipped: vec![],
+            mismatches: vec![],
+        }
+    }
+
+    /// Report for the whole epoch, starting from the first slot of the epoch
+    pub const fn for_epoch(schedule: &EpochSchedule, epoch: u64) -> Self {
+        Self::new(schedule.get_first_slot_in_epoch(epoch))
+    }
+
+    /// Continue report after the block, e.g. on resume from checkpoint
+    pub const fn after_block(slot: u64) -> Self {
+        Self {
+            first_slot: slot,
+            last_slot: Some(slot),
+            blocks: 0,
+            skipped: vec![],
+            mismatches: vec![],
+        }
+    }
+
+    pub fn push_block(&mut self, block: &Block) {
+        self.push(block.slot, block.meta.parent_slot);
+    }
+
+    pub fn push(&mut self, slot: u64, parent_slot: u64) {
+        let expected_parent = self.last_slot;
+        let parent_ok = match expected_parent {
+            Some(previous_slot) => parent_slot == previous_slot,
+            // parent of the first block can be before the archive
+            None => parent_slot < self.first_slot,
+        };
+        if !parent_ok {
+            self.mismatches.push(ParentMismatch {
+                slot,
+                parent_slot,
+                previous_slot: expected_parent,
+            });
+        }
+
+        let from = expected_parent.map_or(self.first_slot, |slot| slot + 1);
+        self.skipped
+            .extend((from..slot).filter(|&skipped| parent_ok || skipped != parent_slot));
+        self.last_slot = Some(slot);
+        self.blocks += 1;
+    }
+
+    /// Mark slots after the last block up to `last_slot` (inclusive) as skipped
+    pub fn finish(&mut self, last_slot: u64) {
+        let from = self.last_slot.map_or(self.first_slot, |slot| slot + 1);
+        self.skipped.extend(from..=last_slot);
+        self.last_slot = Some(self.last_slot.unwrap_or_default().max(last_slot));
+    }
+
+    pub const fn is_complete(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gaps::{GapReport, ParentMismatch};
+
+    #[test]
+    fn test_report() {
+        let mut report = GapReport::new(10);
+        report.push(11, 8);
+        report.push(12, 11);
+        report.push(15, 12);
+        report.push(18, 17);
+        report.finish(20);
+        assert_eq!(report.blocks, 4);
+        assert_eq!(report.skipped, [10, 13, 14, 16, 19, 20]);
+        assert_eq!(
+            report.mismatches,
+            [ParentMismatch {
+                slot: 18,
+                parent_slot: 17,
+                previous_slot: Some(15),
+            }]
+        );
+        assert!(!report.is_complete());
+
+        let mut report = GapReport::after_block(18);
+        report.push(19, 18);
+        assert!(report.skipped.is_empty());
+        assert!(report.is_complete());
+    }
+}
@@ -0,0 +1,402 @@
+#[cfg(feature = "source")]
+use crate::{
+    node::{DecodeMode, NodeWithCid},
+    source::CarSource,
+};
+#[cfg(feature = "solana")]
+use {
+    crate::solana::DecodeError,
+    solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::TransactionError},
+};
+use {
+    crate::{
+        cache::NodeCache,
+        node::{Node, NodeError, NodeReader, NodeReaderOptions, Nodes},
+    },
+    cid::Cid,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+    },
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, SeekFrom},
+};
+#[cfg(all(feature = "source", feature = "solana"))]
+use {
+    crate::{node::Kind, solana::get_hash},
+    solana_sdk::hash::Hash,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexedBlock {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub blocktime: u64,
+    /// Offset of the first section that belongs to the block (right after the previous block)
+    pub offset: u64,
+    /// Length of all sections of the block, including the Block node itself
+    pub length: u64,
+    /// CID and section of the last entry, its hash is the blockhash
+    pub last_entry: Option<(Cid, IndexedSection)>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SlotIndex {
+    pub header: Vec<u8>,
+    pub blocks: BTreeMap<u64, IndexedBlock>,
+    /// Cache shared by `read_block` and `fetch_block`
+    pub cache: Option<Arc<NodeCache>>,
+}
+
+impl SlotIndex {
+    /// Scan the whole CAR file, `on_block` is called for every block with all nodes of it
+    pub async fn build<R, F, E>(reader: R, mut on_block: F) -> Result<Self, E>
+    where
+        R: AsyncRead + Unpin,
+        F: FnMut(&IndexedBlock, &Nodes) -> Result<(), E>,
+        E: From<NodeError>,
+    {
+        let mut reader = NodeReader::new(reader);
+        let mut index = Self {
+            header: reader.read_header().await?.to_vec(),
+            blocks: BTreeMap::new(),
+            cache: None,
+        };
+
+        let mut sections = HashMap::new();
+        loop {
+            let offset = reader.offset();
+            sections.clear();
+            let nodes = Nodes::read_until_block_with(&mut reader, |node| {
+                sections.insert(
+                    node.cid,
+                    IndexedSection {
+                        offset: node.offset(),
+                        length: node.section_len(),
+                    },
+                );
+            })
+            .await?;
+            let Some(Node::Block(block)) = nodes.nodes.values().last() else {
+                break;
+            };
+
+            let block = IndexedBlock {
+                slot: block.slot,
+                parent_slot: block.meta.parent_slot,
+                blocktime: block.meta.blocktime,
+                offset,
+                length: reader.offset() - offset,
+                last_entry: block
+                    .entries
+                    .last()
+                    .and_then(|cid| Some((*cid, *sections.get(cid)?))),
+            };
+            on_block(&block, &nodes)?;
+            index.blocks.insert(block.slot, block);
+        }
+
+        Ok(index)
+    }
+
+    pub fn get(&self, slot: u64) -> Option<&IndexedBlock> {
+        self.blocks.get(&slot)
+    }
+
+    pub fn first_slot(&self) -> Option<u64> {
+        self.blocks.keys().next().copied()
+    }
+
+    pub async fn read_block<R: AsyncRead + AsyncSeek + Unpin>(
+        &self,
+        reader: &mut R,
+        slot: u64,
+    ) -> Result<Option<Nodes>, NodeError> {
+        let Some(block) = self.get(slot) else {
+            return Ok(None);
+        };
+
+        reader.seek(SeekFrom::Start(block.offset)).await?;
+        let mut reader = self.reader_options().build_with_header(
+            reader.take(block.length),
+            self.header.clone(),
+            block.offset,
+        );
+        Nodes::read_until_block(&mut reader).await.map(Some)
+    }
+
+    /// Same as `read_block`, but only the bytes of the block are requested from the source
+    #[cfg(feature = "source")]
+    pub async fn fetch_block<S: CarSource>(
+        &self,
+        source: &S,
+        slot: u64,
+    ) -> Result<Option<Nodes>, NodeError> {
+        let Some(block) = self.get(slot) else {
+            return Ok(None);
+        };
+
+        let data = source.read_at(block.offset, block.length).await?;
+        let mut reader = self.reader_options().build_with_header(
+            data.as_slice(),
+            self.header.clone(),
+            block.offset,
+        );
+        Nodes::read_until_block(&mut reader).await.map(Some)
+    }
+
+    /// Blockhash of the block, only the section of its last entry is requested from the source
+    #[cfg(all(feature = "source", feature = "solana"))]
+    pub async fn fetch_blockhash<S: CarSource>(
+        &self,
+        source: &S,
+        slot: u64,
+    ) -> Result<Option<Hash>, DecodeError> {
+        let Some(block) = self.get(slot) else {
+            return Ok(None);
+        };
+
+        let (cid, section) = block.last_entry.ok_or(DecodeError::MissedEntries(slot))?;
+        match fetch_section(source, &self.header, &section, &cid, DecodeMode::Lenient).await? {
+            Some(NodeWithCid {
+                node: Node::Entry(entry),
+                ..
+            }) => get_hash(&entry.hash).map(Some),
+            Some(node) => Err(DecodeError::invalid_node(&node.node, Kind::Entry)),
+            None => Err(DecodeError::MissedCid(cid)),
+        }
+    }
+
+    fn reader_options(&self) -> NodeReaderOptions {
+        match &self.cache {
+            Some(cache) => NodeReaderOptions::default().cache(Arc::clone(cache)),
+            None => NodeReaderOptions::default(),
+        }
+    }
+}
+
+/// Section of a node, including the length prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexedSection {
+    pub offset: u64,
+    pub length: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CidIndex {
+    pub header: Vec<u8>,
+    pub sections: HashMap<Cid, IndexedSection>,
+}
+
+impl CidIndex {
+    /// Scan the whole CAR file, nodes are not decoded
+    pub async fn build<R: AsyncRead + Unpin>(reader: R) -> Result<Self, NodeError> {
+        let mut reader = NodeReader::new(reader);
+        let mut index = Self {
+            header: reader.read_header().await?.to_vec(),
+            sections: HashMap::new(),
+        };
+        while let Some(node) = reader.read_node().await? {
+            index.sections.insert(
+                node.cid,
+                IndexedSection {
+                    offset: node.offset(),
+                    length: node.section_len(),
+                },
+            );
+        }
+        Ok(index)
+    }
+
+    pub fn get(&self, cid: &Cid) -> Option<&IndexedSection> {
+        self.sections.get(cid)
+    }
+
+    /// Request only the section of the node from the source, the section should contain the
+    /// node with the requested CID
+    #[cfg(feature = "source")]
+    pub async fn fetch_node<S: CarSource>(
+        &self,
+        source: &S,
+        cid: &Cid,
+        mode: DecodeMode,
+    ) -> Result<Option<NodeWithCid>, NodeError> {
+        match self.get(cid) {
+            Some(section) => fetch_section(source, &self.header, section, cid, mode).await,
+            None => Ok(None),
+        }
+    }
+}
+
+/// Request the section from the source, it should contain the node with `cid`
+#[cfg(feature = "source")]
+async fn fetch_section<S: CarSource>(
+    source: &S,
+    header: &[u8],
+    section: &IndexedSection,
+    cid: &Cid,
+    mode: DecodeMode,
+) -> Result<Option<NodeWithCid>, NodeError> {
+    let data = source.read_at(section.offset, section.length).await?;
+    let mut reader = NodeReaderOptions::default().build_with_header(
+        data.as_slice(),
+        header.to_vec(),
+        section.offset,
+    );
+    match reader.read_node().await? {
+        Some(node) if node.cid != *cid => Err(NodeError::UnexpectedCid {
+            expected: Box::new(*cid),
+            found: Box::new(node.cid),
+        }
+        .with_offset(node.offset())),
+        Some(node) => NodeWithCid::decode(&node, mode)
+            .map(Some)
+            .map_err(|error| error.with_offset(node.offset())),
+        None => Ok(None),
+    }
+}
+
+#[cfg(feature = "solana")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedTransaction {
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+}
+
+#[cfg(feature = "solana")]
+#[derive(Debug, Default, Clone)]
+pub struct SignatureIndex {
+    pub transactions: HashMap<Signature, IndexedTransaction>,
+    /// Signatures of transactions for every account in the block order, only static keys and
+    /// loaded addresses are indexed (inner instructions are not)
+    pub addresses: Option<HashMap<Pubkey, Vec<Signature>>>,
+}
+
+#[cfg(feature = "solana")]
+impl SignatureIndex {
+    pub fn new(with_addresses: bool) -> Self {
+        Self {
+            transactions: HashMap::new(),
+            addresses: with_addresses.then(HashMap::new),
+        }
+    }
+
+    pub fn push_block(&mut self, nodes: &Nodes) -> Result<(), DecodeError> {
+        let block = nodes.get_block().ok_or(DecodeError::MissedBlock)?;
+        for transaction in nodes.get_block_transactions(block)? {
+            let tx = nodes.decode_versioned_transaction(transaction)?;
+            let Some(signature) = tx.signatures.first().copied() else {
+                continue;
+            };
+
+            let meta = nodes.decode_transaction_status_meta(transaction)?;
+            if let Some(addresses) = self.addresses.as_mut() {
+                let loaded = meta.iter().flat_map(|meta| {
+                    meta.loaded_addresses
+                        .writable
+                        .iter()
+                        .chain(meta.loaded_addresses.readonly.iter())
+                });
+                for pubkey in tx.message.static_account_keys().iter().chain(loaded) {
+                    addresses.entry(*pubkey).or_default().push(signature);
+                }
+            }
+
+            self.transactions.insert(
+                signature,
+                IndexedTransaction {
+                    slot: block.slot,
+                    err: meta.and_then(|meta| meta.status.err()),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, signature: &Signature) -> Option<&IndexedTransaction> {
+        self.transactions.get(signature)
+    }
+
+    /// Signatures of transactions touching the address, newest first
+    pub fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+    ) -> Option<impl Iterator<Item = &Signature>> {
+        self.addresses.as_ref().map(|addresses| {
+            addresses
+                .get(address)
+                .into_iter()
+                .flat_map(|signatures| signatures.iter().rev())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            index::SlotIndex,
+            node::{Node, NodeError},
+            util::tests::{decode_cid, decode_hex, encode_block, encode_car},
+        },
+        std::io::Cursor,
+    };
+
+    #[tokio::test]
+    async fn test_build_and_read() {
+        let entry1 = decode_cid("bafyreieoodp5usfhjplhph653vpvkpys24meiyz3kvcoacj653yqoqganu");
+        let entry2 = decode_cid("bafyreig6fq5m25b736egoexujhstvhejjj5m5im6i6vlfvwr4ogcukfmny");
+        let car = encode_car(&[
+            (
+                entry1,
+                decode_hex(
+                    "84011930d458203a43cd82e140873740fde924da4125ac30e2fec5eb92344dbb2bb4776973feec80",
+                ),
+            ),
+            (
+                decode_cid("bafyreif7ll4bmg3nvk2n3gws7lmecdwmuw2esyktbbmtoijuu2f2fzqndy"),
+                encode_block(1, &[entry1]),
+            ),
+            (
+                entry2,
+                decode_hex(
+                    "84011930d45820b12c324e55fb861ce6ef0d315ed3115bea52f6bec83cf09c9872c70de69fdfea80",
+                ),
+            ),
+            (
+                decode_cid("bafyreihg5wf2azztumc63bbputua2pgkvd2jus7catewuil6jjc7gprrai"),
+                encode_block(3, &[entry2]),
+            ),
+        ]);
+
+        let mut slots = vec![];
+        let index = SlotIndex::build(Cursor::new(&car), |block, nodes| {
+            slots.push((block.slot, nodes.nodes.len()));
+            Ok::<(), NodeError>(())
+        })
+        .await
+        .expect("valid CAR");
+        assert_eq!(slots, vec![(1, 2), (3, 2)]);
+        assert_eq!(index.first_slot(), Some(1));
+        assert_eq!(
+            index.get(1).unwrap().offset + index.get(1).unwrap().length,
+            index.get(3).unwrap().offset
+        );
+        assert_eq!(
+            index.get(3).unwrap().offset + index.get(3).unwrap().length,
+            car.len() as u64
+        );
+
+        let mut reader = Cursor::new(&car);
+        let nodes = index
+            .read_block(&mut reader, 3)
+            .await
+            .expect("valid block")
+            .expect("block exists");
+        assert!(matches!(nodes.nodes.get(&entry2), Some(Node::Entry(_))));
+        assert!(matches!(
+            nodes.nodes.values().last(),
+            Some(Node::Block(block)) if block.slot == 3
+        ));
+        assert!(index.read_block(&mut reader, 2).await.unwrap().is_none());
+    }
+}
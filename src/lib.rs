@@ -1,3 +1,26 @@
+pub mod cache;
+#[cfg(feature = "source")]
+pub mod catalog;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "replay")]
+pub mod executor;
+#[cfg(feature = "parquet")]
+pub mod export;
+#[cfg(feature = "solana")]
+pub mod filter;
+pub mod gaps;
+pub mod index;
 pub mod node;
+#[cfg(feature = "solana")]
+pub mod replay;
+pub mod resolver;
+#[cfg(feature = "server")]
+pub mod rpc;
+pub mod schedule;
+#[cfg(feature = "solana")]
+pub mod solana;
+#[cfg(feature = "source")]
+pub mod source;
 pub mod util;
 pub mod varint;
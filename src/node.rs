@@ -1,15 +1,27 @@
 use {
-    crate::{util, varint},
-    cid::Cid,
+    crate::{
+        cache::NodeCache,
+        resolver::{self, CacheResolver},
+        util, varint,
+    },
+    bytes::Bytes,
+    cid::{Cid, Version},
     crc::{CRC_64_GO_ISO, Crc},
     fnv::FnvHasher,
     indexmap::IndexMap,
-    std::{fmt, hash::Hasher, io},
+    std::{
+        borrow::Cow,
+        collections::{HashSet, VecDeque},
+        fmt,
+        hash::Hasher,
+        io,
+        sync::Arc,
+    },
     thiserror::Error,
-    tokio::io::AsyncRead,
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, SeekFrom},
 };
 pub use {
-    block::{Block, Shredding, SlotMeta},
+    block::{Block, DATA_SHREDS_PER_FEC_BLOCK, EntryShreds, Shredding, SlotMeta},
     dataframe::DataFrame,
     entry::Entry,
     epoch::Epoch,
@@ -26,8 +38,29 @@ mod rewards;
 mod subset;
 mod transaction;
 
-const MAX_ALLOWED_HEADER_SIZE: usize = 1024;
-const MAX_ALLOWED_SECTION_SIZE: usize = 32 << 20; // 32MiB
+pub const DEFAULT_MAX_HEADER_SIZE: usize = 1024;
+pub const DEFAULT_MAX_SECTION_SIZE: usize = 32 << 20; // 32MiB
+
+/// Multicodec code of DAG-CBOR, used by all Old Faithful nodes
+pub(crate) const DAG_CBOR: u64 = 0x71;
+/// Identity CIDs inline the data and are never written as sections
+#[cfg(any(feature = "verify-cid", feature = "solana"))]
+pub(crate) const MULTIHASH_IDENTITY: u64 = 0x00;
+pub(crate) const MULTIHASH_SHA2_256: u64 = 0x12;
+/// Max length of section length prefix and CID prefix (version, codec, hash function, digest length)
+const MAX_SECTION_PREFIX_LEN: usize = 4 * 10;
+/// Chunk size used to scan for the next section in recovery mode
+const RECOVERY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Schema checks applied while decoding nodes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeMode {
+    /// Missing fields are left default and integers are truncated, as required by legacy archives
+    #[default]
+    Lenient,
+    /// Required fields should be present, integers should fit and no extra fields are allowed
+    Strict,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Kind {
@@ -68,6 +101,11 @@ impl Kind {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", rename_all = "lowercase")
+)]
 pub enum Node {
     Transaction(transaction::Transaction),
     Entry(entry::Entry),
@@ -82,7 +120,13 @@ impl TryFrom<&[u8]> for Node {
     type Error = NodeError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let value = serde_cbor::from_slice::<serde_cbor::Value>(value)?;
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Node {
+    pub fn decode(data: &[u8], mode: DecodeMode) -> Result<Self, NodeError> {
+        let value = serde_cbor::from_slice::<serde_cbor::Value>(data)?;
 
         let mut kind = None;
         if let serde_cbor::Value::Array(vec) = &value {
@@ -95,18 +139,16 @@ impl TryFrom<&[u8]> for Node {
         };
 
         Ok(match kind {
-            Kind::Transaction => Node::Transaction(Transaction::try_from(value)?),
-            Kind::Entry => Node::Entry(Entry::try_from(value)?),
-            Kind::Block => Node::Block(Block::try_from(value)?),
-            Kind::Subset => Node::Subset(Subset::try_from(value)?),
-            Kind::Epoch => Node::Epoch(Epoch::try_from(value)?),
-            Kind::Rewards => Node::Rewards(Rewards::try_from(value)?),
-            Kind::DataFrame => Node::DataFrame(DataFrame::try_from(value)?),
+            Kind::Transaction => Node::Transaction(Transaction::decode(value, mode)?),
+            Kind::Entry => Node::Entry(Entry::decode(value, mode)?),
+            Kind::Block => Node::Block(Block::decode(value, mode)?),
+            Kind::Subset => Node::Subset(Subset::decode(value, mode)?),
+            Kind::Epoch => Node::Epoch(Epoch::decode(value, mode)?),
+            Kind::Rewards => Node::Rewards(Rewards::decode(value, mode)?),
+            Kind::DataFrame => Node::DataFrame(DataFrame::decode(value, mode)?),
         })
     }
-}
 
-impl Node {
     pub const fn kind(&self) -> Kind {
         match self {
             Self::Transaction(_) => Kind::Transaction,
@@ -121,8 +163,11 @@ impl Node {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NodeWithCid {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cid"))]
     pub cid: Cid,
+    #[cfg_attr(feature = "serde", serde(flatten))]
     pub node: Node,
 }
 
@@ -130,9 +175,15 @@ impl TryFrom<&RawNode> for NodeWithCid {
     type Error = NodeError;
 
     fn try_from(value: &RawNode) -> Result<Self, Self::Error> {
-        Node::try_from(value.get_data()).map(|node| Self {
-            cid: value.cid,
-            node,
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl NodeWithCid {
+    pub fn decode(node: &RawNode, mode: DecodeMode) -> Result<Self, NodeError> {
+        Node::decode(node.get_data(), mode).map(|decoded| Self {
+            cid: node.cid,
+            node: decoded,
         })
     }
 }
@@ -142,6 +193,8 @@ pub struct RawNode {
     pub cid: Cid,
     data: Vec<u8>,
     data_offset: usize,
+    offset: u64,
+    section_len: u64,
 }
 
 impl RawNode {
@@ -150,6 +203,8 @@ impl RawNode {
             cid,
             data,
             data_offset: 0,
+            offset: 0,
+            section_len: 0,
         }
     }
 
@@ -183,41 +238,227 @@ impl RawNode {
             cid,
             data,
             data_offset,
+            offset: 0,
+            section_len: 0,
         })
     }
 
     pub fn get_data(&self) -> &[u8] {
         &self.data[self.data_offset..]
     }
+
+    /// Offset of the section (starting with the length prefix) in the CAR file, zero if the node
+    /// was not read by `NodeReader`
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Length of the section (including the length prefix) in the CAR file, zero if the node was
+    /// not read by `NodeReader`
+    pub const fn section_len(&self) -> u64 {
+        self.section_len
+    }
+}
+
+/// Limits and checks applied by `NodeReader`
+#[derive(Debug, Clone)]
+pub struct NodeReaderOptions {
+    max_header_size: usize,
+    max_section_size: usize,
+    #[cfg(feature = "verify-cid")]
+    verify_cid: bool,
+    cid_versions: Option<Vec<Version>>,
+    cid_codecs: Option<Vec<u64>>,
+    decode_mode: DecodeMode,
+    cache: Option<Arc<NodeCache>>,
+}
+
+impl Default for NodeReaderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeReaderOptions {
+    pub const fn new() -> Self {
+        Self {
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_section_size: DEFAULT_MAX_SECTION_SIZE,
+            #[cfg(feature = "verify-cid")]
+            verify_cid: false,
+            cid_versions: None,
+            cid_codecs: None,
+            decode_mode: DecodeMode::Lenient,
+            cache: None,
+        }
+    }
+
+    pub const fn max_header_size(mut self, size: usize) -> Self {
+        self.max_header_size = size;
+        self
+    }
+
+    pub const fn max_section_size(mut self, size: usize) -> Self {
+        self.max_section_size = size;
+        self
+    }
+
+    /// Hash data of every section and compare it with the CID (sha2-256 and identity only)
+    #[cfg(feature = "verify-cid")]
+    pub const fn verify_cid(mut self, verify: bool) -> Self {
+        self.verify_cid = verify;
+        self
+    }
+
+    /// Allowed CID versions, any version is allowed by default
+    pub fn cid_versions(mut self, versions: impl IntoIterator<Item = Version>) -> Self {
+        self.cid_versions = Some(versions.into_iter().collect());
+        self
+    }
+
+    /// Allowed multicodec codes, any codec is allowed by default
+    pub fn cid_codecs(mut self, codecs: impl IntoIterator<Item = u64>) -> Self {
+        self.cid_codecs = Some(codecs.into_iter().collect());
+        self
+    }
+
+    /// Mode used by `Nodes::read_until_block` and `NodeReader::read_node_recover`
+    pub const fn decode_mode(mut self, mode: DecodeMode) -> Self {
+        self.decode_mode = mode;
+        self
+    }
+
+    /// Decoded nodes are looked up in the cache and added to it by `Nodes::read_until_block`
+    /// and `NodeReader::read_node_recover`
+    pub fn cache(mut self, cache: Arc<NodeCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub const fn build<R: AsyncRead + Unpin>(self, reader: R) -> NodeReader<R> {
+        NodeReader::with_options(reader, self)
+    }
+
+    /// Create reader positioned at a section boundary `offset`, with the header read before
+    pub const fn build_with_header<R: AsyncRead + Unpin>(
+        self,
+        reader: R,
+        header: Vec<u8>,
+        offset: u64,
+    ) -> NodeReader<R> {
+        NodeReader {
+            reader,
+            header,
+            offset,
+            options: self,
+        }
+    }
+
+    fn decode_node(&self, node: &RawNode) -> Result<NodeWithCid, NodeError> {
+        match &self.cache {
+            Some(cache) => cache
+                .decode(node, self.decode_mode)
+                .map(|decoded| NodeWithCid {
+                    cid: node.cid,
+                    node: Arc::unwrap_or_clone(decoded),
+                }),
+            None => NodeWithCid::decode(node, self.decode_mode),
+        }
+    }
+
+    fn check_node(&self, node: &RawNode) -> Result<(), NodeError> {
+        let version = node.cid.version();
+        if let Some(versions) = &self.cid_versions {
+            if !versions.contains(&version) {
+                return Err(NodeError::CidVersionNotAllowed(version.into()));
+            }
+        }
+
+        let codec = node.cid.codec();
+        if let Some(codecs) = &self.cid_codecs {
+            if !codecs.contains(&codec) {
+                return Err(NodeError::CidCodecNotAllowed(codec));
+            }
+        }
+
+        #[cfg(feature = "verify-cid")]
+        if self.verify_cid {
+            use sha2::{Digest, Sha256};
+
+            let hash = node.cid.hash();
+            let digest = match hash.code() {
+                MULTIHASH_IDENTITY => node.get_data().to_vec(),
+                MULTIHASH_SHA2_256 => Sha256::digest(node.get_data()).to_vec(),
+                code => return Err(NodeError::UnsupportedMultihash(code)),
+            };
+            if digest != hash.digest() {
+                return Err(NodeError::CidMismatch(node.cid));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 pub struct NodeReader<R> {
     reader: R,
     header: Vec<u8>,
+    offset: u64,
+    options: NodeReaderOptions,
 }
 
 impl<R> fmt::Debug for NodeReader<R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("NodeReader").finish()
+        f.debug_struct("NodeReader")
+            .field("offset", &self.offset)
+            .finish()
     }
 }
 
 impl<R: AsyncRead + Unpin> NodeReader<R> {
     pub const fn new(reader: R) -> Self {
+        Self::with_options(reader, NodeReaderOptions::new())
+    }
+
+    pub const fn with_options(reader: R, options: NodeReaderOptions) -> Self {
         Self {
             reader,
             header: vec![],
+            offset: 0,
+            options,
         }
     }
 
+    pub const fn options(&self) -> &NodeReaderOptions {
+        &self.options
+    }
+
+    pub const fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Offset of the next section in the CAR file (the end of the header once it is read)
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
     pub async fn read_header(&mut self) -> Result<&[u8], NodeError> {
         if self.header.is_empty() {
-            let header_length = varint::read(&mut self.reader).await? as usize;
-            if header_length > MAX_ALLOWED_HEADER_SIZE {
-                return Err(NodeError::HeaderTooLong(header_length));
+            let (header_length, varint_length) = varint::read_with_len(&mut self.reader).await?;
+            let header_length = header_length as usize;
+            // empty header would be read again on every `read_node`
+            if header_length == 0 {
+                return Err(NodeError::EmptyHeader);
+            }
+            if header_length > self.options.max_header_size {
+                return Err(NodeError::HeaderTooLong {
+                    size: header_length,
+                    max: self.options.max_header_size,
+                });
             }
 
             self.header = util::read_exact(&mut self.reader, header_length).await?;
+            self.offset = (varint_length + header_length) as u64;
         }
 
         Ok(&self.header)
@@ -228,21 +469,159 @@ impl<R: AsyncRead + Unpin> NodeReader<R> {
             self.read_header().await?;
         };
 
+        let offset = self.offset;
+        self.read_section()
+            .await
+            .map_err(|error| error.with_offset(offset))
+    }
+
+    async fn read_section(&mut self) -> Result<Option<RawNode>, NodeError> {
         // read and decode the uvarint prefix (length of CID + data)
-        let section_size = match varint::read(&mut self.reader).await {
-            Ok(size) => size as usize,
+        let (section_size, varint_length) = match varint::read_with_len(&mut self.reader).await {
+            Ok((size, length)) => (size as usize, length),
             Err(varint::VarIntError::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
                 return Ok(None);
             }
             Err(error) => return Err(error.into()),
         };
-        if section_size > MAX_ALLOWED_SECTION_SIZE {
-            return Err(NodeError::SectionTooLong(section_size));
+        if section_size > self.options.max_section_size {
+            return Err(NodeError::SectionTooLong {
+                size: section_size,
+                max: self.options.max_section_size,
+            });
         }
 
         let section = util::read_exact(&mut self.reader, section_size).await?;
-        RawNode::new_from_vec(section).map(Some)
+        // the section is consumed even if it can not be decoded
+        let offset = self.offset;
+        let section_len = (varint_length + section_size) as u64;
+        self.offset += section_len;
+
+        let mut node = RawNode::new_from_vec(section)?;
+        node.offset = offset;
+        node.section_len = section_len;
+        self.options.check_node(&node)?;
+        Ok(Some(node))
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> NodeReader<R> {
+    /// Move to a section boundary `offset`, e.g. previously saved with `NodeReader::offset`,
+    /// the header is read first if required
+    pub async fn seek(&mut self, offset: u64) -> Result<(), NodeError> {
+        self.read_header().await?;
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Read node in recovery mode: if a section can not be read or decoded, the reader scans
+    /// forward for the next plausible section boundary and continues from it, every skipped
+    /// region is passed to `on_skip` once the next node is read (or the input is over)
+    pub async fn read_node_recover<F: FnMut(SkippedSection)>(
+        &mut self,
+        mut on_skip: F,
+    ) -> Result<Option<NodeWithCid>, NodeError> {
+        self.read_header().await?;
+        // consecutive failures (including false boundaries) are reported as one region
+        let mut skipped: Option<SkippedSection> = None;
+        loop {
+            let offset = self.offset;
+            let error = match self.read_node().await {
+                Ok(Some(node)) => match self.options.decode_node(&node) {
+                    Ok(node) => {
+                        if let Some(skipped) = skipped {
+                            on_skip(skipped);
+                        }
+                        return Ok(Some(node));
+                    }
+                    Err(error) => error.with_offset(offset),
+                },
+                Ok(None) => {
+                    if let Some(skipped) = skipped {
+                        on_skip(skipped);
+                    }
+                    return Ok(None);
+                }
+                Err(error) if error.is_fatal_io() => return Err(error),
+                Err(error) => error,
+            };
+
+            let next = self.find_section(offset + 1).await?;
+            self.reader.seek(SeekFrom::Start(next)).await?;
+            self.offset = next;
+            let skipped = skipped.get_or_insert(SkippedSection {
+                offset,
+                skipped: 0,
+                error,
+            });
+            skipped.skipped = next - skipped.offset;
+        }
     }
+
+    /// Returns offset of the next plausible section start or the end of the input
+    async fn find_section(&mut self, offset: u64) -> Result<u64, NodeError> {
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+        let mut position = offset;
+        let mut buffer = Vec::with_capacity(RECOVERY_CHUNK_SIZE + MAX_SECTION_PREFIX_LEN);
+        loop {
+            let read = (&mut self.reader)
+                .take(RECOVERY_CHUNK_SIZE as u64)
+                .read_to_end(&mut buffer)
+                .await?;
+            let eof = read < RECOVERY_CHUNK_SIZE;
+
+            // keep the tail, a section prefix can be split between chunks
+            let scan = if eof {
+                buffer.len()
+            } else {
+                buffer.len().saturating_sub(MAX_SECTION_PREFIX_LEN)
+            };
+            let max_section_size = self.options.max_section_size;
+            if let Some(index) =
+                (0..scan).find(|&index| is_section_start(&buffer[index..], max_section_size))
+            {
+                return Ok(position + index as u64);
+            }
+            if eof {
+                return Ok(position + buffer.len() as u64);
+            }
+
+            buffer.drain(..scan);
+            position += scan as u64;
+        }
+    }
+}
+
+/// Plausible section start: length prefix followed by CIDv1 DAG-CBOR prefix that fits the section
+fn is_section_start(section: &[u8], max_section_size: usize) -> bool {
+    let mut buf = section;
+    let Ok(length) = varint::decode_varint(&mut buf) else {
+        return false;
+    };
+    let start = buf.len();
+    let (Ok(1), Ok(DAG_CBOR), Ok(_hash_function), Ok(digest_length)) = (
+        varint::decode_varint(&mut buf),
+        varint::decode_varint(&mut buf),
+        varint::decode_varint(&mut buf),
+        varint::decode_varint(&mut buf),
+    ) else {
+        return false;
+    };
+    // digest length is checked first, so a hostile value can not overflow the CID length
+    digest_length <= 64
+        && (start - buf.len()) as u64 + digest_length < length
+        && length <= max_section_size as u64
+}
+
+/// Region skipped by `NodeReader::read_node_recover`
+#[derive(Debug)]
+pub struct SkippedSection {
+    /// Offset of the section that failed
+    pub offset: u64,
+    /// Number of bytes skipped to the next plausible section or the end of the input
+    pub skipped: u64,
+    pub error: NodeError,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -251,8 +630,37 @@ pub struct Nodes {
 }
 
 impl Nodes {
+    /// Same as `read_until_block`, but corrupted sections are skipped with
+    /// `NodeReader::read_node_recover`
+    pub async fn read_until_block_recover<R, F>(
+        reader: &mut NodeReader<R>,
+        mut on_skip: F,
+    ) -> Result<Self, NodeError>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+        F: FnMut(SkippedSection),
+    {
+        let mut block = Self::default();
+        while let Some(node) = reader.read_node_recover(&mut on_skip).await? {
+            let finished = matches!(node.node, Node::Block(_));
+            block.push(node);
+            if finished {
+                break;
+            }
+        }
+        Ok(block)
+    }
+
     pub async fn read_until_block<R: AsyncRead + Unpin>(
         reader: &mut NodeReader<R>,
+    ) -> Result<Self, NodeError> {
+        Self::read_until_block_with(reader, |_node| {}).await
+    }
+
+    /// Same as `read_until_block`, `on_node` is called with every section before it is decoded
+    pub(crate) async fn read_until_block_with<R: AsyncRead + Unpin, F: FnMut(&RawNode)>(
+        reader: &mut NodeReader<R>,
+        mut on_node: F,
     ) -> Result<Self, NodeError> {
         let mut block = Self::default();
         let mut finished = false;
@@ -260,7 +668,11 @@ impl Nodes {
             let Some(node) = reader.read_node().await? else {
                 break;
             };
-            let node = NodeWithCid::try_from(&node)?;
+            on_node(&node);
+            let node = reader
+                .options
+                .decode_node(&node)
+                .map_err(|error| error.with_offset(node.offset()))?;
             finished = matches!(node.node, Node::Block(_));
             block.push(node);
         }
@@ -275,65 +687,232 @@ impl Nodes {
         &'a self,
         dataframe: &'a DataFrame,
     ) -> Result<Vec<u8>, ReassableError> {
-        let mut dataframes = vec![dataframe];
-        let total = dataframe.total.unwrap_or_default() as usize;
+        resolver::resolved(resolver::reassemble_dataframes(self, dataframe))
+    }
+
+    /// Same as `reassemble_dataframes`, but the payload is read from the frames on demand
+    pub fn dataframe_reader<'a>(
+        &'a self,
+        dataframe: &'a DataFrame,
+    ) -> Result<DataFrameReader<'a>, ReassableError> {
+        resolver::resolved(resolver::dataframe_reader(self, dataframe))
+    }
+
+    /// Same as `reassemble_dataframes`, but the payload is cached and frames missed in the
+    /// block are looked up in the cache (nodes decoded in `mode` only)
+    pub fn reassemble_dataframes_cached(
+        &self,
+        dataframe: &DataFrame,
+        cache: &NodeCache,
+        mode: DecodeMode,
+    ) -> Result<Bytes, ReassableError> {
+        let resolver = (self, CacheResolver::new(cache).decode_mode(mode));
+        resolver::resolved(resolver::reassemble_payload(&resolver, dataframe))
+    }
+}
+
+/// Frames collected by following `DataFrame::next` links from the first frame
+pub(crate) struct DataFrames<'a> {
+    first: &'a DataFrame,
+    total: usize,
+    frames: Vec<Cow<'a, DataFrame>>,
+    next_cids: Vec<Cid>,
+    visited: HashSet<Cid>,
+}
+
+impl<'a> DataFrames<'a> {
+    pub(crate) fn new(first: &'a DataFrame) -> Result<Self, ReassableError> {
+        let total = first.total.unwrap_or_default() as usize;
+        let mut dataframes = Self {
+            first,
+            total,
+            frames: vec![Cow::Borrowed(first)],
+            next_cids: vec![],
+            visited: HashSet::new(),
+        };
         if total > 1 {
-            let mut next_cids = dataframe.next.clone();
-            while let Some(cid) = next_cids.pop() {
-                let Some(node) = self.nodes.get(&cid) else {
-                    return Err(ReassableError::MissedCid(cid));
-                };
-                let Node::DataFrame(dataframe) = node else {
-                    return Err(ReassableError::InvalidNode(node.kind()));
-                };
-                next_cids.extend(&dataframe.next);
-                dataframes.push(dataframe);
+            dataframes.queue(&first.next)?;
+        }
+        Ok(dataframes)
+    }
+
+    /// Link that should be resolved and pushed next
+    pub(crate) fn next_cid(&mut self) -> Option<Cid> {
+        self.next_cids.pop()
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        cid: Cid,
+        node: Option<Cow<'a, Node>>,
+    ) -> Result<(), ReassableError> {
+        let dataframe = match node {
+            Some(Cow::Borrowed(Node::DataFrame(dataframe))) => Cow::Borrowed(dataframe),
+            Some(Cow::Owned(Node::DataFrame(dataframe))) => Cow::Owned(dataframe),
+            Some(node) => return Err(ReassableError::InvalidNode(node.kind())),
+            None => return Err(ReassableError::MissedCid(cid)),
+        };
+        self.queue(&dataframe.next)?;
+        self.frames.push(dataframe);
+        Ok(())
+    }
+
+    /// Every frame but the first is linked exactly once, so frames that can be reached are
+    /// counted by visited links and checked against `total` before anything is resolved
+    fn queue(&mut self, next: &[Cid]) -> Result<(), ReassableError> {
+        for cid in next {
+            if !self.visited.insert(*cid) {
+                return Err(ReassableError::DuplicatedDataFrame(*cid));
             }
-            if dataframes.len() != total {
+            self.next_cids.push(*cid);
+            let found = self.visited.len() + 1;
+            if found > self.total {
                 return Err(ReassableError::InvalidNumberOfDataFrames {
-                    expected: total,
-                    found: dataframes.len(),
+                    expected: self.total,
+                    found,
                 });
             }
-            dataframes.sort_by_key(|frame| frame.index.unwrap_or_default());
         }
+        Ok(())
+    }
 
-        let data: Vec<u8> = dataframes
-            .iter()
-            .flat_map(|frame| frame.data.iter())
-            .copied()
-            .collect();
-
-        if let Some(expected) = dataframe.hash {
-            let crc64 = get_crc64(&data);
-            if crc64 != expected {
-                // maybe it's the legacy checksum function?
-                let fnv = get_fnv(&data);
-                if fnv != expected {
-                    return Err(ReassableError::InvalidHash {
-                        crc64,
-                        fnv,
-                        expected,
-                    });
-                }
+    pub(crate) fn into_reader(mut self) -> Result<DataFrameReader<'a>, ReassableError> {
+        if self.total > 1 {
+            if self.frames.len() != self.total {
+                return Err(ReassableError::InvalidNumberOfDataFrames {
+                    expected: self.total,
+                    found: self.frames.len(),
+                });
             }
+            self.frames
+                .sort_by_key(|frame| frame.index.unwrap_or_default());
         }
 
-        Ok(data)
+        Ok(DataFrameReader {
+            frames: self.frames.into(),
+            position: 0,
+            crc64: self.first.hash.map(|_| CRC_64.digest()),
+            fnv: FnvHasher::default(),
+            expected: self.first.hash,
+            mismatch: None,
+        })
     }
 }
 
-fn get_crc64(data: &[u8]) -> u64 {
-    let crc = Crc::<u64>::new(&CRC_64_GO_ISO);
-    let mut digest = crc.digest();
-    digest.update(data);
-    digest.finalize()
+static CRC_64: Crc<u64> = Crc::<u64>::new(&CRC_64_GO_ISO);
+
+/// Payload of ordered frames read without copying it into a single buffer, the hash is
+/// computed while reading and validated at the end of the payload (`io::ErrorKind::InvalidData`
+/// with `ReassableError::InvalidHash` inside)
+pub struct DataFrameReader<'a> {
+    frames: VecDeque<Cow<'a, DataFrame>>,
+    /// Position in the first frame
+    position: usize,
+    /// `None` if the payload has no hash or it is already validated
+    crc64: Option<crc::Digest<'static, u64>>,
+    fnv: FnvHasher,
+    expected: Option<u64>,
+    /// `crc64` and `fnv` of the payload that failed validation, returned by every later read
+    mismatch: Option<(u64, u64)>,
+}
+
+impl fmt::Debug for DataFrameReader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataFrameReader")
+            .field("remaining", &self.remaining())
+            .field("expected", &self.expected)
+            .finish()
+    }
 }
 
-fn get_fnv(data: &[u8]) -> u64 {
-    let mut hasher = FnvHasher::default();
-    hasher.write(data);
-    hasher.finish()
+impl DataFrameReader<'_> {
+    /// Number of bytes left in the payload
+    pub fn remaining(&self) -> usize {
+        let total = self
+            .frames
+            .iter()
+            .map(|frame| frame.data.len())
+            .sum::<usize>();
+        total - self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Skip the rest of the payload and validate the hash
+    pub fn finish(mut self) -> Result<(), ReassableError> {
+        self.drain(|_chunk| {});
+        self.validate()
+    }
+
+    /// Read the rest of the payload into a single buffer and validate the hash
+    pub(crate) fn into_vec(mut self) -> Result<Vec<u8>, ReassableError> {
+        let mut data = Vec::with_capacity(self.remaining());
+        self.drain(|chunk| data.extend_from_slice(chunk));
+        self.validate().map(|()| data)
+    }
+
+    fn drain<F: FnMut(&[u8])>(&mut self, mut on_chunk: F) {
+        while let Some(frame) = self.frames.pop_front() {
+            let chunk = &frame.data[self.position..];
+            Self::update(&mut self.crc64, &mut self.fnv, chunk);
+            on_chunk(chunk);
+            self.position = 0;
+        }
+    }
+
+    fn update(crc64: &mut Option<crc::Digest<'static, u64>>, fnv: &mut FnvHasher, chunk: &[u8]) {
+        if let Some(crc64) = crc64 {
+            crc64.update(chunk);
+            fnv.write(chunk);
+        }
+    }
+
+    fn validate(&mut self) -> Result<(), ReassableError> {
+        if let (Some((crc64, fnv)), Some(expected)) = (self.mismatch, self.expected) {
+            return Err(ReassableError::InvalidHash {
+                crc64,
+                fnv,
+                expected,
+            });
+        }
+        let (Some(digest), Some(expected)) = (self.crc64.take(), self.expected) else {
+            return Ok(());
+        };
+
+        let crc64 = digest.finalize();
+        // maybe it's the legacy checksum function?
+        let fnv = self.fnv.finish();
+        if crc64 != expected && fnv != expected {
+            self.mismatch = Some((crc64, fnv));
+            return self.validate();
+        }
+        Ok(())
+    }
+}
+
+impl io::Read for DataFrameReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(frame) = self.frames.front() {
+            let data = &frame.data[self.position..];
+            if data.is_empty() {
+                self.frames.pop_front();
+                self.position = 0;
+                continue;
+            }
+
+            let size = data.len().min(buf.len());
+            buf[..size].copy_from_slice(&data[..size]);
+            Self::update(&mut self.crc64, &mut self.fnv, &data[..size]);
+            self.position += size;
+            return Ok(size);
+        }
+
+        self.validate()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(0)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -343,10 +922,12 @@ pub enum NodeError {
     Io(#[from] io::Error),
     #[error("invalid varint")]
     InvalidVarInt,
-    #[error("header size is too long: {0} (max {max})", max = MAX_ALLOWED_HEADER_SIZE)]
-    HeaderTooLong(usize),
-    #[error("section size is too long: {0} (max {max})", max = MAX_ALLOWED_SECTION_SIZE)]
-    SectionTooLong(usize),
+    #[error("empty header")]
+    EmptyHeader,
+    #[error("header size is too long: {size} (max {max})")]
+    HeaderTooLong { size: usize, max: usize },
+    #[error("section size is too long: {size} (max {max})")]
+    SectionTooLong { size: usize, max: usize },
     // deserialize
     #[error(transparent)]
     DeserializeCbor(#[from] serde_cbor::Error),
@@ -355,8 +936,26 @@ pub enum NodeError {
         path: &'static str,
         kind: &'static str,
     },
+    #[error("invalid link for {path:?}: {error}")]
+    InvalidLink {
+        path: &'static str,
+        error: LinkError,
+    },
     #[error("too much items in the array")]
     UnexpectedCborValues,
+    #[error("missed required field {path}::{field}")]
+    MissedField {
+        path: &'static str,
+        field: &'static str,
+    },
+    #[error("expected at most {max} fields for {path:?}, found {count}")]
+    UnexpectedFieldCount {
+        path: &'static str,
+        count: usize,
+        max: usize,
+    },
+    #[error("integer {value} is out of range for {path:?}")]
+    IntegerOutOfRange { path: &'static str, value: i128 },
     #[error("invalid node kind: {node:?} (expected: {expected:?})")]
     InvalidKind { node: u64, expected: u64 },
     #[error("unknown node kind: {0:?}")]
@@ -369,6 +968,19 @@ pub enum NodeError {
     MultihashNotEnoughBytes,
     #[error(transparent)]
     InvalidMultihash(#[from] multihash::Error),
+    #[error("cid version is not allowed: {0}")]
+    CidVersionNotAllowed(u64),
+    #[error("cid codec is not allowed: {0:#x}")]
+    CidCodecNotAllowed(u64),
+    #[error("unsupported multihash for cid verification: {0:#x}")]
+    UnsupportedMultihash(u64),
+    #[error("data does not match cid: {0}")]
+    CidMismatch(Cid),
+    #[error("section has cid {found} (expected: {expected})")]
+    UnexpectedCid { expected: Box<Cid>, found: Box<Cid> },
+    // context
+    #[error("section at offset {offset}: {error}")]
+    Section { offset: u64, error: Box<NodeError> },
 }
 
 impl From<varint::VarIntError> for NodeError {
@@ -381,6 +993,34 @@ impl From<varint::VarIntError> for NodeError {
 }
 
 impl NodeError {
+    /// Attach offset of the section that failed to read or decode
+    pub fn with_offset(self, offset: u64) -> Self {
+        match self {
+            Self::Section { .. } => self,
+            error => Self::Section {
+                offset,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// IO error other than unexpected EOF, these can not be recovered by skipping bytes
+    fn is_fatal_io(&self) -> bool {
+        match self {
+            Self::Io(error) => error.kind() != io::ErrorKind::UnexpectedEof,
+            Self::Section { error, .. } => error.is_fatal_io(),
+            _ => false,
+        }
+    }
+
+    /// Offset of the failed section, if known
+    pub const fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Section { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
     #[inline]
     pub const fn assert_invalid_kind(node: u64, expected: Kind) -> Result<(), Self> {
         if node == expected.to_u64() {
@@ -394,14 +1034,448 @@ impl NodeError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ShreddingError {
+    #[error("expected {expected} shredding items, found {found}")]
+    InvalidLength { expected: usize, found: usize },
+    #[error("shredding {index} has entry end index {entry_end_idx}")]
+    InvalidEntryIndex { index: usize, entry_end_idx: i64 },
+    #[error("shredding {index} has shred end index {shred_end_idx} after {previous}")]
+    InvalidShredIndex {
+        index: usize,
+        shred_end_idx: i64,
+        previous: i64,
+    },
+    #[error("last entry {index} does not end a batch")]
+    Unterminated { index: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum LinkError {
+    #[error("expected CBOR tag 42, found {0:?}")]
+    UnexpectedTag(Option<u64>),
+    #[error("expected Bytes in CBOR tag 42")]
+    ExpectedBytes,
+    #[error("empty link")]
+    Empty,
+    #[error(transparent)]
+    InvalidCid(#[from] cid::Error),
+}
+
 #[derive(Debug, Error)]
 pub enum ReassableError {
     #[error("missed cid: {0}")]
     MissedCid(Cid),
     #[error("expected {expected} DataFrames, found: {found}")]
     InvalidNumberOfDataFrames { expected: usize, found: usize },
+    #[error("DataFrame linked more than once: {0}")]
+    DuplicatedDataFrame(Cid),
     #[error("invalid node kind: {0:?} (expected DataFrame)")]
     InvalidNode(Kind),
     #[error("invalid hash: crc64/{crc64} fnv/{fnv} (expected: {expected}")]
     InvalidHash { crc64: u64, fnv: u64, expected: u64 },
+    #[error(transparent)]
+    Resolve(#[from] NodeError),
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            node::{
+                DataFrame, DecodeMode, Epoch, LinkError, Node, NodeError, NodeReader,
+                NodeReaderOptions, Nodes, RawNode, ReassableError,
+            },
+            util::tests::{decode_cid, decode_hex, encode_car, encode_cid, encode_link},
+        },
+        cid::{Cid, Version},
+        crc::{CRC_64_GO_ISO, Crc},
+        fnv::FnvHasher,
+        serde_cbor::Value,
+        std::{
+            fs,
+            hash::Hasher,
+            io::{self, Cursor, Read},
+            path::Path,
+        },
+    };
+
+    fn create_car() -> (Cid, Cid, Vec<u8>) {
+        let entry1 = decode_hex(
+            "84011930d458203a43cd82e140873740fde924da4125ac30e2fec5eb92344dbb2bb4776973feec80",
+        );
+        let entry2 = decode_hex(
+            "84011930d45820b12c324e55fb861ce6ef0d315ed3115bea52f6bec83cf09c9872c70de69fdfea80",
+        );
+        let (cid1, cid2) = (encode_cid(&entry1), encode_cid(&entry2));
+        (cid1, cid2, encode_car(&[(cid1, entry1), (cid2, entry2)]))
+    }
+
+    #[tokio::test]
+    async fn test_offset_and_seek() {
+        let (entry1, entry2, car) = create_car();
+
+        let mut reader = NodeReader::new(Cursor::new(&car));
+        let header_length = reader.read_header().await.unwrap().len() as u64;
+        assert_eq!(reader.offset(), 1 + header_length);
+        let node = reader.read_node().await.unwrap().unwrap();
+        assert_eq!(node.cid, entry1);
+        assert_eq!(node.offset(), 1 + header_length);
+        assert_eq!(node.offset() + node.section_len(), reader.offset());
+        let offset = reader.offset();
+        assert_eq!(reader.read_node().await.unwrap().unwrap().cid, entry2);
+        assert_eq!(reader.offset(), car.len() as u64);
+        assert!(reader.read_node().await.unwrap().is_none());
+
+        let mut reader = NodeReader::new(Cursor::new(&car));
+        reader.seek(offset).await.unwrap();
+        assert_eq!(reader.read_node().await.unwrap().unwrap().cid, entry2);
+        assert_eq!(reader.offset(), car.len() as u64);
+
+        let mut reader = NodeReader::new(Cursor::new(&car[..car.len() - 1]));
+        reader.read_node().await.unwrap();
+        let error = reader.read_node().await.unwrap_err();
+        assert_eq!(error.offset(), Some(offset));
+
+        // undecodable section is consumed, the next read starts after it
+        let mut car = car.clone();
+        car[offset as usize - node.section_len() as usize + 1] = 0x05;
+        let mut reader = NodeReader::new(Cursor::new(&car));
+        let error = reader.read_node().await.unwrap_err();
+        assert_eq!(error.offset(), Some(node.offset()));
+        assert_eq!(reader.offset(), offset);
+        assert_eq!(reader.read_node().await.unwrap().unwrap().cid, entry2);
+
+        assert_eq!(RawNode::new(entry1, vec![]).section_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_options() {
+        let (entry1, _entry2, car) = create_car();
+        let mut reader = NodeReaderOptions::default()
+            .cid_versions([Version::V1])
+            .build(Cursor::new(&car));
+        assert_eq!(reader.read_node().await.unwrap().unwrap().cid, entry1);
+
+        let mut reader = NodeReaderOptions::default()
+            .max_section_size(16)
+            .build(Cursor::new(&car));
+        assert!(matches!(
+            reader.read_node().await,
+            Err(NodeError::Section { ref error, .. })
+                if matches!(**error, NodeError::SectionTooLong { max: 16, .. })
+        ));
+
+        let mut reader = NodeReaderOptions::default()
+            .cid_codecs([0x55])
+            .build(Cursor::new(&car));
+        assert!(matches!(
+            reader.read_node().await,
+            Err(NodeError::Section { ref error, .. })
+                if matches!(**error, NodeError::CidCodecNotAllowed(0x71))
+        ));
+
+        #[cfg(feature = "verify-cid")]
+        {
+            let options = NodeReaderOptions::default().verify_cid(true);
+            let mut reader = options.clone().build(Cursor::new(&car));
+            assert_eq!(reader.read_node().await.unwrap().unwrap().cid, entry1);
+
+            let mut car = car.clone();
+            let last = car.len() - 1;
+            car[last] ^= 0xff;
+            let mut reader = options.build(Cursor::new(&car));
+            reader.read_node().await.unwrap();
+            assert!(matches!(
+                reader.read_node().await,
+                Err(NodeError::Section { ref error, .. })
+                    if matches!(**error, NodeError::CidMismatch(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_mode() {
+        let block = |slot: i128, meta: Vec<Value>, rewards: Option<Value>| {
+            let mut fields = vec![
+                Value::Integer(2),
+                Value::Integer(slot),
+                Value::Array(vec![]),
+                Value::Array(vec![]),
+                Value::Array(meta),
+            ];
+            fields.extend(rewards);
+            serde_cbor::to_vec(&Value::Array(fields)).unwrap()
+        };
+        let rewards = encode_link(&decode_cid("bafkqaaa"));
+        let meta = vec![Value::Integer(9), Value::Integer(0)];
+
+        let valid = block(10, meta.clone(), Some(rewards.clone()));
+        for mode in [DecodeMode::Lenient, DecodeMode::Strict] {
+            assert!(matches!(Node::decode(&valid, mode), Ok(Node::Block(_))));
+        }
+
+        let missed = block(10, meta.clone(), None);
+        assert!(Node::decode(&missed, DecodeMode::Lenient).is_ok());
+        assert!(matches!(
+            Node::decode(&missed, DecodeMode::Strict),
+            Err(NodeError::MissedField {
+                path: "Block",
+                field: "rewards"
+            })
+        ));
+
+        let negative = block(-1, meta.clone(), Some(rewards.clone()));
+        assert!(matches!(
+            Node::decode(&negative, DecodeMode::Lenient),
+            Ok(Node::Block(block)) if block.slot == u64::MAX
+        ));
+        assert!(matches!(
+            Node::decode(&negative, DecodeMode::Strict),
+            Err(NodeError::IntegerOutOfRange {
+                path: "Block::slot",
+                value: -1
+            })
+        ));
+
+        let mut extra = meta;
+        extra.extend([Value::Null, Value::Null]);
+        assert!(matches!(
+            Node::decode(&block(10, extra, Some(rewards)), DecodeMode::Strict),
+            Err(NodeError::UnexpectedFieldCount {
+                path: "SlotMeta",
+                count: 4,
+                max: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_links() {
+        let epoch = |link: Value| {
+            Epoch::try_from(Value::Array(vec![
+                Value::Integer(4),
+                Value::Integer(1),
+                Value::Array(vec![link]),
+            ]))
+        };
+        let cid = decode_cid("bafyreig6fq5m25b736egoexujhstvhejjj5m5im6i6vlfvwr4ogcukfmny");
+        let tag = |bytes: Vec<u8>| Value::Tag(42, Box::new(Value::Bytes(bytes)));
+
+        assert_eq!(epoch(encode_link(&cid)).unwrap().subsets, [cid]);
+        assert_eq!(epoch(tag(cid.to_bytes())).unwrap().subsets, [cid]);
+
+        for (link, expected) in [
+            (Value::Bytes(cid.to_bytes()), LinkError::UnexpectedTag(None)),
+            (
+                Value::Tag(43, Box::new(Value::Bytes(cid.to_bytes()))),
+                LinkError::UnexpectedTag(Some(43)),
+            ),
+            (
+                Value::Tag(42, Box::new(Value::Null)),
+                LinkError::ExpectedBytes,
+            ),
+            (tag(vec![]), LinkError::Empty),
+        ] {
+            match epoch(link) {
+                Err(NodeError::InvalidLink {
+                    path: "Epoch::subsets[]",
+                    error,
+                }) => assert_eq!(error.to_string(), expected.to_string()),
+                result => panic!("unexpected result: {result:?}"),
+            }
+        }
+        assert!(matches!(
+            epoch(tag(vec![0, 1])),
+            Err(NodeError::InvalidLink {
+                error: LinkError::InvalidCid(_),
+                ..
+            })
+        ));
+    }
+
+    /// Inputs of `fuzz/corpus`, every target should return an error or a value without panic
+    #[tokio::test]
+    async fn test_fuzz_corpus() {
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus");
+        for target in ["raw_node", "node", "node_reader"] {
+            for entry in fs::read_dir(corpus.join(target)).unwrap() {
+                let data = fs::read(entry.unwrap().path()).unwrap();
+
+                if let Ok(node) = RawNode::new_from_vec(data.clone()) {
+                    let _ = Node::try_from(node.get_data());
+                }
+
+                for mode in [DecodeMode::Lenient, DecodeMode::Strict] {
+                    let _ = Node::decode(&data, mode);
+                }
+
+                let options = NodeReaderOptions::default();
+                #[cfg(feature = "verify-cid")]
+                let options = options.verify_cid(true);
+                let mut reader = options
+                    .decode_mode(DecodeMode::Strict)
+                    .build(Cursor::new(&data));
+                while let Ok(nodes) = Nodes::read_until_block(&mut reader).await {
+                    if nodes.nodes.is_empty() {
+                        break;
+                    }
+                }
+                let mut reader = NodeReader::new(Cursor::new(&data));
+                while let Ok(nodes) = Nodes::read_until_block(&mut reader).await {
+                    if nodes.nodes.is_empty() {
+                        break;
+                    }
+                    for node in nodes.nodes.values() {
+                        if let Node::DataFrame(dataframe) = node {
+                            let _ = nodes.reassemble_dataframes(dataframe);
+                        }
+                    }
+                }
+                let mut reader = NodeReader::new(Cursor::new(&data));
+                while let Ok(Some(_node)) = reader.read_node_recover(|_skipped| {}).await {}
+            }
+        }
+
+        // CIDs are not verified by default, so frames can link to themselves
+        let cid = decode_cid("bafkqaaa");
+        for total in [2, u64::MAX] {
+            let frame = DataFrame {
+                total: Some(total),
+                next: vec![cid],
+                ..Default::default()
+            };
+            let mut nodes = Nodes::default();
+            nodes.nodes.insert(cid, Node::DataFrame(frame.clone()));
+            assert!(matches!(
+                nodes.reassemble_dataframes(&frame),
+                Err(ReassableError::DuplicatedDataFrame(found)) if found == cid
+            ));
+        }
+
+        // links are rejected once they reach more frames than `total`
+        let frame = DataFrame {
+            total: Some(2),
+            next: vec![encode_cid(b"frame1"), encode_cid(b"frame2")],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Nodes::default().reassemble_dataframes(&frame),
+            Err(ReassableError::InvalidNumberOfDataFrames {
+                expected: 2,
+                found: 3
+            })
+        ));
+
+        // frames linked twice are resolved once, so chains can't grow exponentially
+        let (frame1, frame2) = (encode_cid(b"frame1"), encode_cid(b"frame2"));
+        let frame = DataFrame {
+            total: Some(u64::MAX),
+            next: vec![frame1],
+            ..Default::default()
+        };
+        let mut nodes = Nodes::default();
+        let linked = DataFrame {
+            next: vec![frame2, frame2],
+            ..Default::default()
+        };
+        nodes.nodes.insert(frame1, Node::DataFrame(linked));
+        assert!(matches!(
+            nodes.reassemble_dataframes(&frame),
+            Err(ReassableError::DuplicatedDataFrame(found)) if found == frame2
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recover() {
+        let (entry1, entry2, mut car) = create_car();
+        let mut reader = NodeReader::new(Cursor::new(&car));
+        let section = reader.read_node().await.unwrap().unwrap();
+        assert_eq!(section.cid, entry1);
+        // unknown CID version
+        car[section.offset() as usize + 1] = 0x05;
+
+        let mut skipped = vec![];
+        let mut reader = NodeReader::new(Cursor::new(&car));
+        let node = reader
+            .read_node_recover(|skip| skipped.push(skip))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(node.cid, entry2);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].offset, section.offset());
+        assert_eq!(skipped[0].skipped, section.section_len());
+        assert!(matches!(
+            skipped[0].error,
+            NodeError::Section { ref error, .. } if matches!(**error, NodeError::UnknownCid(5))
+        ));
+
+        // truncated last section is skipped to the end of the input
+        let mut skipped = vec![];
+        let mut reader = NodeReader::new(Cursor::new(&car[..car.len() - 1]));
+        let node = reader
+            .read_node_recover(|skip| skipped.push(skip))
+            .await
+            .unwrap();
+        assert!(node.is_none());
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].offset, section.offset());
+        assert_eq!(skipped[0].offset + skipped[0].skipped, car.len() as u64 - 1);
+    }
+
+    #[test]
+    fn test_dataframe_reader() {
+        let cids = [encode_cid(b"frame 1"), encode_cid(b"frame 2")];
+        let mut nodes = Nodes::default();
+        for (cid, index, data) in [(cids[0], 2, "rld"), (cids[1], 1, "lo wo")] {
+            nodes.nodes.insert(
+                cid,
+                Node::DataFrame(DataFrame {
+                    index: Some(index),
+                    total: Some(3),
+                    data: data.as_bytes().to_vec(),
+                    ..Default::default()
+                }),
+            );
+        }
+        let frame = |hash| DataFrame {
+            hash: Some(hash),
+            index: Some(0),
+            total: Some(3),
+            data: b"hel".to_vec(),
+            next: cids.to_vec(),
+        };
+
+        let crc64 = Crc::<u64>::new(&CRC_64_GO_ISO).checksum(b"hello world");
+        let mut fnv = FnvHasher::default();
+        fnv.write(b"hello world");
+        for hash in [crc64, fnv.finish()] {
+            let frame = frame(hash);
+            let mut reader = nodes.dataframe_reader(&frame).unwrap();
+            assert_eq!(reader.remaining(), 11);
+            let (mut data, mut buf) = (vec![], [0; 2]);
+            while let Ok(size @ 1..) = reader.read(&mut buf) {
+                data.extend_from_slice(&buf[..size]);
+            }
+            assert_eq!(data, b"hello world");
+            assert!(reader.is_empty());
+        }
+
+        // the mismatch is returned by every read after the end of the payload
+        let frame = frame(crc64 + 1);
+        let mut reader = nodes.dataframe_reader(&frame).unwrap();
+        for _ in 0..2 {
+            let error = reader.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(matches!(
+                error.downcast::<ReassableError>(),
+                Ok(ReassableError::InvalidHash { .. })
+            ));
+        }
+        assert!(matches!(
+            nodes.dataframe_reader(&frame).unwrap().finish(),
+            Err(ReassableError::InvalidHash { .. })
+        ));
+    }
 }
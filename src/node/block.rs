@@ -1,11 +1,15 @@
 use {
     crate::{
-        node::{Kind, NodeError},
+        node::{DecodeMode, Kind, NodeError, ShreddingError},
         util,
     },
     cid::Cid,
+    std::ops::RangeInclusive,
 };
 
+/// Data shreds per FEC set with merkle shreds
+pub const DATA_SHREDS_PER_FEC_BLOCK: u32 = 32;
+
 // type Block struct {
 //   kind      Int
 //   # The slot number where this block was created.
@@ -18,11 +22,14 @@ use {
 //   rewards   Link     # &Rewards
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Block {
     pub slot: u64,
     pub shredding: Vec<Shredding>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cids"))]
     pub entries: Vec<Cid>,
     pub meta: SlotMeta,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cid"))]
     pub rewards: Cid,
 }
 
@@ -38,27 +45,34 @@ impl TryFrom<serde_cbor::Value> for Block {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Block {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 6] = ["kind", "slot", "shredding", "entries", "meta", "rewards"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Block")?
+        for (index, value) in util::cbor::get_fields(value, "Block", &FIELDS, 6, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "Block::kind")? as u64,
+                    util::cbor::get_u64(value, "Block::kind", mode)?,
                     Kind::Block,
                 )?,
-                1 => node.slot = util::cbor::get_int(value, "Block::slot")? as u64,
+                1 => node.slot = util::cbor::get_u64(value, "Block::slot", mode)?,
                 2 => {
                     for value in util::cbor::get_array(value, "Block::shredding")? {
-                        node.shredding.push(Shredding::try_from(value)?);
+                        node.shredding.push(Shredding::decode(value, mode)?);
                     }
                 }
                 3 => {
                     node.entries =
                         util::cbor::get_array_cids(value, "Block::entries", "Block::entries[]")?
                 }
-                4 => node.meta = SlotMeta::try_from(value)?,
+                4 => node.meta = SlotMeta::decode(value, mode)?,
                 5 => node.rewards = util::cbor::get_cid(value, "Block::rewards")?,
                 _ => return Err(NodeError::UnexpectedCborValues),
             }
@@ -67,11 +81,89 @@ impl TryFrom<serde_cbor::Value> for Block {
     }
 }
 
+impl Block {
+    /// Data shreds of the batch that contains each entry of `Block::entries`, shredding should
+    /// have an item per entry with shred indices increasing from batch to batch and the last
+    /// entry should end a batch
+    pub fn get_entry_shreds(&self) -> Result<Vec<EntryShreds>, ShreddingError> {
+        if self.shredding.len() != self.entries.len() {
+            return Err(ShreddingError::InvalidLength {
+                expected: self.entries.len(),
+                found: self.shredding.len(),
+            });
+        }
+
+        let mut shreds = Vec::with_capacity(self.shredding.len());
+        let mut batch_start = 0;
+        let mut previous_end = -1;
+        for (index, shredding) in self.shredding.iter().enumerate() {
+            if shredding.entry_end_idx != index as i64 {
+                return Err(ShreddingError::InvalidEntryIndex {
+                    index,
+                    entry_end_idx: shredding.entry_end_idx,
+                });
+            }
+            if shredding.shred_end_idx == -1 {
+                continue;
+            }
+            if shredding.shred_end_idx <= previous_end || shredding.shred_end_idx > u32::MAX as i64
+            {
+                return Err(ShreddingError::InvalidShredIndex {
+                    index,
+                    shred_end_idx: shredding.shred_end_idx,
+                    previous: previous_end,
+                });
+            }
+
+            let range = (previous_end + 1) as u32..=shredding.shred_end_idx as u32;
+            let batch = shreds
+                .last()
+                .map_or(0, |entry: &EntryShreds| entry.batch + 1);
+            shreds.extend((batch_start..=index).map(|entry| EntryShreds {
+                entry,
+                batch,
+                shreds: range.clone(),
+                batch_end: entry == index,
+            }));
+            batch_start = index + 1;
+            previous_end = shredding.shred_end_idx;
+        }
+
+        if batch_start != self.entries.len() {
+            return Err(ShreddingError::Unterminated {
+                index: self.entries.len() - 1,
+            });
+        }
+        Ok(shreds)
+    }
+}
+
+/// Data shreds of the entry batch (entries serialized together) that contains the entry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntryShreds {
+    /// Index in `Block::entries`
+    pub entry: usize,
+    /// Index of the batch in the block
+    pub batch: usize,
+    /// Data shred indices, the last shred is marked as data complete
+    pub shreds: RangeInclusive<u32>,
+    /// The entry is the last one in the batch
+    pub batch_end: bool,
+}
+
+impl EntryShreds {
+    /// FEC set of the last shred of the batch, valid for blocks with fixed size FEC sets
+    pub const fn fec_set_index(&self) -> u32 {
+        *self.shreds.end() / DATA_SHREDS_PER_FEC_BLOCK * DATA_SHREDS_PER_FEC_BLOCK
+    }
+}
+
 // type Shredding struct {
 //   entryEndIdx Int
 //   shredEndIdx Int
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Shredding {
     pub entry_end_idx: i64,
     pub shred_end_idx: i64,
@@ -81,19 +173,26 @@ impl TryFrom<serde_cbor::Value> for Shredding {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Shredding {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 2] = ["entry_end_idx", "shred_end_idx"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Shredding")?
+        for (index, value) in util::cbor::get_fields(value, "Shredding", &FIELDS, 2, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => {
                     node.entry_end_idx =
-                        util::cbor::get_int(value, "Shredding::entry_end_idx")? as i64
+                        util::cbor::get_i64(value, "Shredding::entry_end_idx", mode)?
                 }
                 1 => {
                     node.shred_end_idx =
-                        util::cbor::get_int(value, "Shredding::shred_end_idx")? as i64
+                        util::cbor::get_i64(value, "Shredding::shred_end_idx", mode)?
                 }
                 _ => return Err(NodeError::UnexpectedCborValues),
             }
@@ -111,6 +210,7 @@ impl TryFrom<serde_cbor::Value> for Shredding {
 //   block_height nullable optional Int
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SlotMeta {
     pub parent_slot: u64,
     pub blocktime: u64,
@@ -121,17 +221,24 @@ impl TryFrom<serde_cbor::Value> for SlotMeta {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl SlotMeta {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 3] = ["parent_slot", "blocktime", "block_height"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "SlotMeta")?
+        for (index, value) in util::cbor::get_fields(value, "SlotMeta", &FIELDS, 2, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
-                0 => node.parent_slot = util::cbor::get_int(value, "SlotMeta::parent_slot")? as u64,
-                1 => node.blocktime = util::cbor::get_int(value, "SlotMeta::blocktime")? as u64,
+                0 => node.parent_slot = util::cbor::get_u64(value, "SlotMeta::parent_slot", mode)?,
+                1 => node.blocktime = util::cbor::get_u64(value, "SlotMeta::blocktime", mode)?,
                 2 => {
                     node.block_height =
-                        util::cbor::get_int_opt(value, "SlotMeta::block_height")?.map(|v| v as u64)
+                        util::cbor::get_u64_opt(value, "SlotMeta::block_height", mode)?
                 }
                 _ => return Err(NodeError::UnexpectedCborValues),
             }
@@ -143,10 +250,79 @@ impl TryFrom<serde_cbor::Value> for SlotMeta {
 #[cfg(test)]
 mod tests {
     use crate::{
-        node::{Block, Shredding, SlotMeta},
+        node::{Block, EntryShreds, Shredding, ShreddingError, SlotMeta},
         util::tests::{decode_cid, decode_cids, decode_hex},
     };
 
+    #[test]
+    fn test_entry_shreds() {
+        let block = |shredding: &[(i64, i64)]| Block {
+            shredding: shredding
+                .iter()
+                .map(|&(entry_end_idx, shred_end_idx)| Shredding {
+                    entry_end_idx,
+                    shred_end_idx,
+                })
+                .collect(),
+            entries: vec![decode_cid("bafkqaaa"); shredding.len()],
+            ..Default::default()
+        };
+
+        let shreds = block(&[(0, -1), (1, 3), (2, -1), (3, 40)])
+            .get_entry_shreds()
+            .expect("valid shredding");
+        assert_eq!(
+            shreds,
+            [
+                (0, 0, 0..=3, false),
+                (1, 0, 0..=3, true),
+                (2, 1, 4..=40, false),
+                (3, 1, 4..=40, true),
+            ]
+            .map(|(entry, batch, shreds, batch_end)| EntryShreds {
+                entry,
+                batch,
+                shreds,
+                batch_end,
+            })
+        );
+        assert_eq!(shreds[0].fec_set_index(), 0);
+        assert_eq!(shreds[3].fec_set_index(), 32);
+
+        let mut missed = block(&[(0, 0), (1, 1)]);
+        missed.entries.push(decode_cid("bafkqaaa"));
+        for (block, error) in [
+            (
+                missed,
+                ShreddingError::InvalidLength {
+                    expected: 3,
+                    found: 2,
+                },
+            ),
+            (
+                block(&[(0, 0), (2, 1)]),
+                ShreddingError::InvalidEntryIndex {
+                    index: 1,
+                    entry_end_idx: 2,
+                },
+            ),
+            (
+                block(&[(0, 5), (1, 5)]),
+                ShreddingError::InvalidShredIndex {
+                    index: 1,
+                    shred_end_idx: 5,
+                    previous: 5,
+                },
+            ),
+            (
+                block(&[(0, 0), (1, -1)]),
+                ShreddingError::Unterminated { index: 1 },
+            ),
+        ] {
+            assert_eq!(block.get_entry_shreds(), Err(error));
+        }
+    }
+
     #[test]
     fn test_decoding() {
         for (bytes, frame) in [
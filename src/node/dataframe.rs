@@ -1,9 +1,12 @@
 use {
     crate::{
-        node::{Kind, NodeError},
+        node::{DAG_CBOR, DecodeMode, Kind, MULTIHASH_SHA2_256, NodeError},
         util,
     },
     cid::Cid,
+    multihash::Multihash,
+    serde_cbor::Value,
+    sha2::{Digest, Sha256},
 };
 
 // # DataFrame is a chunk of data that is part of a larger whole. It contains
@@ -44,11 +47,14 @@ use {
 //   next nullable optional  [ Link ] # [ &DataFrame ]
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DataFrame {
     pub hash: Option<u64>,
     pub index: Option<u64>,
     pub total: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::base64"))]
     pub data: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cids"))]
     pub next: Vec<Cid>,
 }
 
@@ -64,27 +70,54 @@ impl TryFrom<serde_cbor::Value> for DataFrame {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl DataFrame {
+    /// CID of the frame encoded as a DAG-CBOR node, the first frame is embedded in its parent
+    /// node and has no CID in the CAR file
+    pub fn cid(&self) -> Cid {
+        // Old Faithful writes integers as int64, so large hashes are negative
+        let int = |value: Option<i64>| value.map_or(Value::Null, |value| value.into());
+        let value = Value::Array(vec![
+            Value::Integer(Kind::DataFrame as i128),
+            int(self.hash.map(|hash| hash as i64)),
+            int(self.index.map(|index| index as i64)),
+            int(self.total.map(|total| total as i64)),
+            Value::Bytes(self.data.clone()),
+            Value::Array(
+                self.next
+                    .iter()
+                    .map(|cid| {
+                        let mut bytes = vec![0];
+                        bytes.extend(cid.to_bytes());
+                        Value::Tag(42, Box::new(Value::Bytes(bytes)))
+                    })
+                    .collect(),
+            ),
+        ]);
+        let data = serde_cbor::to_vec(&value).expect("DataFrame is encodable");
+        let hash = Multihash::wrap(MULTIHASH_SHA2_256, &Sha256::digest(data))
+            .expect("SHA2-256 digest fits multihash");
+        Cid::new_v1(DAG_CBOR, hash)
+    }
+
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 6] = ["kind", "hash", "index", "total", "data", "next"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "DataFrame")?
+        for (index, value) in util::cbor::get_fields(value, "DataFrame", &FIELDS, 5, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "DataFrame::kind")? as u64,
+                    util::cbor::get_u64(value, "DataFrame::kind", mode)?,
                     Kind::DataFrame,
                 )?,
-                1 => {
-                    node.hash = util::cbor::get_int_opt(value, "DataFrame::hash")?.map(|v| v as u64)
-                }
-                2 => {
-                    node.index =
-                        util::cbor::get_int_opt(value, "DataFrame::index")?.map(|v| v as u64)
-                }
-                3 => {
-                    node.total =
-                        util::cbor::get_int_opt(value, "DataFrame::total")?.map(|v| v as u64)
-                }
+                1 => node.hash = util::cbor::get_u64_opt(value, "DataFrame::hash", mode)?,
+                2 => node.index = util::cbor::get_u64_opt(value, "DataFrame::index", mode)?,
+                3 => node.total = util::cbor::get_u64_opt(value, "DataFrame::total", mode)?,
                 4 => node.data = util::cbor::get_bytes(value, "DataFrame::data")?,
                 5 => {
                     node.next = util::cbor::get_array_opt(value, "DataFrame::next")?
@@ -104,7 +137,7 @@ impl TryFrom<serde_cbor::Value> for DataFrame {
 mod tests {
     use crate::{
         node::DataFrame,
-        util::tests::{decode_cids, decode_hex},
+        util::tests::{decode_cids, decode_hex, encode_cid},
     };
 
     #[test]
@@ -151,6 +184,32 @@ mod tests {
         ] {
             let node = DataFrame::try_from(bytes.as_ref()).expect("valid node");
             assert_eq!(node, frame);
+            assert_eq!(node.cid(), encode_cid(&bytes));
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let node = DataFrame::try_from(decode_hex(
+            "86061b48acf56598bd34f816181c4a6fedb3ada52763ab71e985d82a582500017112207a470286e1843dbaa2ffb81d30018a40e8c3bb14026b6085fd63d49fd6eb1fb0d82a582500017112201c8cb9aa3b528a23d7d53a8ee3521f9223e6a791f3d6bb88e01fcae192f5e5c6d82a582500017112206bc71f7272fb4138de6cf336b63fc2b23dc5450480473e74de2b69fa0eb6af3cd82a582500017112205732ff009530b6506437a05cc070885fba4da69ff40bd30c6febbb7c1d349266d82a5825000171122051d872d71e7a36e28bc4361c852c805bc7102f2989bed661966c41f2d933314f",
+        ).as_ref())
+        .expect("valid node");
+        assert_eq!(
+            serde_json::to_value(&node).expect("valid json"),
+            serde_json::json!({
+                "hash": 5236830283428082936u64,
+                "index": 22,
+                "total": 28,
+                "data": "b+2zraUnY6tx6Q==",
+                "next": [
+                    "bafyreid2i4binymehw5kf75yduyadcsa5db3wfacnnqil7ld2sp5n2y7wa",
+                    "bafyreia4rs42uo2srir5pvj2r3rveh4septkpept225yrya7zlqzf5pfyy",
+                    "bafyreidly4pxe4x3ie4n43htg23d7qvshxcukbeai47hjxrlnh5a5nvphq",
+                    "bafyreicxgl7qbfjqwzigin5altahbcc7xjg2nh7ubpjqy37lxn6b2nesmy",
+                    "bafyreicr3bznoht2g3rixrbwdscszac3y4ic6kmjx3lgdftmihznsmzrj4",
+                ],
+            })
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use {
     crate::{
-        node::{Kind, NodeError},
+        node::{DecodeMode, Kind, NodeError},
         util,
     },
     cid::Cid,
@@ -14,9 +14,12 @@ use {
 //   transactions [ Link ] # [ &Transaction ]
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Entry {
     pub num_hashes: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::base58"))]
     pub hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cids"))]
     pub transactions: Vec<Cid>,
 }
 
@@ -32,17 +35,24 @@ impl TryFrom<serde_cbor::Value> for Entry {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Entry {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 4] = ["kind", "num_hashes", "hash", "transactions"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Entry")?
+        for (index, value) in util::cbor::get_fields(value, "Entry", &FIELDS, 4, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "Entry::kind")? as u64,
+                    util::cbor::get_u64(value, "Entry::kind", mode)?,
                     Kind::Entry,
                 )?,
-                1 => node.num_hashes = util::cbor::get_int(value, "Entry::num_hashes")? as u64,
+                1 => node.num_hashes = util::cbor::get_u64(value, "Entry::num_hashes", mode)?,
                 2 => node.hash = util::cbor::get_bytes(value, "Entry::hash")?,
                 3 => {
                     node.transactions = util::cbor::get_array_cids(
@@ -1,6 +1,6 @@
 use {
     crate::{
-        node::{Kind, NodeError},
+        node::{DecodeMode, Kind, NodeError},
         util,
     },
     cid::Cid,
@@ -20,8 +20,10 @@ use {
 //   subsets [ Link ] # [ &Subset ]
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Epoch {
     pub epoch: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cids"))]
     pub subsets: Vec<Cid>,
 }
 
@@ -37,17 +39,24 @@ impl TryFrom<serde_cbor::Value> for Epoch {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Epoch {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 3] = ["kind", "epoch", "subsets"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Epoch")?
+        for (index, value) in util::cbor::get_fields(value, "Epoch", &FIELDS, 3, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "Epoch::kind")? as u64,
+                    util::cbor::get_u64(value, "Epoch::kind", mode)?,
                     Kind::Epoch,
                 )?,
-                1 => node.epoch = util::cbor::get_int(value, "Epoch::epoch")? as u64,
+                1 => node.epoch = util::cbor::get_u64(value, "Epoch::epoch", mode)?,
                 2 => {
                     node.subsets =
                         util::cbor::get_array_cids(value, "Epoch::subsets", "Epoch::subsets[]")?;
@@ -1,5 +1,5 @@
 use crate::{
-    node::{DataFrame, Kind, NodeError},
+    node::{DataFrame, DecodeMode, Kind, NodeError},
     util,
 };
 
@@ -11,6 +11,7 @@ use crate::{
 //   data       DataFrame
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Rewards {
     pub slot: u64,
     pub data: DataFrame,
@@ -28,18 +29,25 @@ impl TryFrom<serde_cbor::Value> for Rewards {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Rewards {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 3] = ["kind", "slot", "data"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Rewards")?
+        for (index, value) in util::cbor::get_fields(value, "Rewards", &FIELDS, 3, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "Rewards::kind")? as u64,
+                    util::cbor::get_u64(value, "Rewards::kind", mode)?,
                     Kind::Rewards,
                 )?,
-                1 => node.slot = util::cbor::get_int(value, "Rewards::slot")? as u64,
-                2 => node.data = DataFrame::try_from(value)?,
+                1 => node.slot = util::cbor::get_u64(value, "Rewards::slot", mode)?,
+                2 => node.data = DataFrame::decode(value, mode)?,
                 _ => return Err(NodeError::UnexpectedCborValues),
             }
         }
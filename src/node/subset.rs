@@ -1,6 +1,6 @@
 use {
     crate::{
-        node::{Kind, NodeError},
+        node::{DecodeMode, Kind, NodeError},
         util,
     },
     cid::Cid,
@@ -16,9 +16,11 @@ use {
 //   blocks [ Link ] # [ &Block ]
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Subset {
     pub first: u64,
     pub last: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "util::serde::cids"))]
     pub blocks: Vec<Cid>,
 }
 
@@ -34,18 +36,25 @@ impl TryFrom<serde_cbor::Value> for Subset {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Subset {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 4] = ["kind", "first", "last", "blocks"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Subset")?
+        for (index, value) in util::cbor::get_fields(value, "Subset", &FIELDS, 4, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "Subset::kind")? as u64,
+                    util::cbor::get_u64(value, "Subset::kind", mode)?,
                     Kind::Subset,
                 )?,
-                1 => node.first = util::cbor::get_int(value, "Subset::first")? as u64,
-                2 => node.last = util::cbor::get_int(value, "Subset::last")? as u64,
+                1 => node.first = util::cbor::get_u64(value, "Subset::first", mode)?,
+                2 => node.last = util::cbor::get_u64(value, "Subset::last", mode)?,
                 3 => {
                     node.blocks =
                         util::cbor::get_array_cids(value, "Subset::blocks", "Subset::blocks[]")?
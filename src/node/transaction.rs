@@ -1,5 +1,5 @@
 use crate::{
-    node::{DataFrame, Kind, NodeError},
+    node::{DataFrame, DecodeMode, Kind, NodeError},
     util,
 };
 
@@ -15,6 +15,7 @@ use crate::{
 //   index nullable optional  Int
 // } representation tuple
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Transaction {
     pub data: DataFrame,
     pub metadata: DataFrame,
@@ -34,23 +35,27 @@ impl TryFrom<serde_cbor::Value> for Transaction {
     type Error = NodeError;
 
     fn try_from(value: serde_cbor::Value) -> Result<Self, Self::Error> {
+        Self::decode(value, DecodeMode::Lenient)
+    }
+}
+
+impl Transaction {
+    pub fn decode(value: serde_cbor::Value, mode: DecodeMode) -> Result<Self, NodeError> {
+        const FIELDS: [&str; 5] = ["kind", "data", "metadata", "slot", "index"];
         let mut node = Self::default();
-        for (index, value) in util::cbor::get_array(value, "Transaction")?
+        for (index, value) in util::cbor::get_fields(value, "Transaction", &FIELDS, 4, mode)?
             .into_iter()
             .enumerate()
         {
             match index {
                 0 => NodeError::assert_invalid_kind(
-                    util::cbor::get_int(value, "Transaction::kind")? as u64,
+                    util::cbor::get_u64(value, "Transaction::kind", mode)?,
                     Kind::Transaction,
                 )?,
-                1 => node.data = DataFrame::try_from(value)?,
-                2 => node.metadata = DataFrame::try_from(value)?,
-                3 => node.slot = util::cbor::get_int(value, "Transaction::slot")? as u64,
-                4 => {
-                    node.index =
-                        util::cbor::get_int_opt(value, "Transaction::index")?.map(|v| v as u64)
-                }
+                1 => node.data = DataFrame::decode(value, mode)?,
+                2 => node.metadata = DataFrame::decode(value, mode)?,
+                3 => node.slot = util::cbor::get_u64(value, "Transaction::slot", mode)?,
+                4 => node.index = util::cbor::get_u64_opt(value, "Transaction::index", mode)?,
                 _ => return Err(NodeError::UnexpectedCborValues),
             }
         }
@@ -0,0 +1,222 @@
+use {
+    crate::{node::Nodes, solana::DecodeError},
+    solana_sdk::{
+        message::SimpleAddressLoader,
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::{MessageHash, SanitizedTransaction, TransactionError},
+    },
+    solana_transaction_status::TransactionStatusMeta,
+    std::{collections::HashSet, error::Error as StdError},
+    thiserror::Error,
+};
+
+/// Archived transaction with the metadata recorded by the cluster
+#[derive(Debug, Clone)]
+pub struct ReplayTransaction {
+    pub transaction: SanitizedTransaction,
+    pub meta: TransactionStatusMeta,
+}
+
+/// Outcome of transaction execution compared by `replay_block`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionResult {
+    pub fee: u64,
+    pub status: Result<(), TransactionError>,
+    pub post_balances: Vec<u64>,
+    /// `None` if logs were not recorded
+    pub log_messages: Option<Vec<String>>,
+}
+
+impl From<&TransactionStatusMeta> for ExecutionResult {
+    fn from(meta: &TransactionStatusMeta) -> Self {
+        Self {
+            fee: meta.fee,
+            status: meta.status.clone(),
+            post_balances: meta.post_balances.clone(),
+            log_messages: meta.log_messages.clone(),
+        }
+    }
+}
+
+/// Executes transactions of a block in order, usually an in-process bank or SVM with accounts
+/// loaded from a snapshot taken before the block
+pub trait ReplayExecutor {
+    type Error: Into<Box<dyn StdError + Send + Sync>>;
+
+    fn execute_block(
+        &mut self,
+        slot: u64,
+        transactions: &[SanitizedTransaction],
+    ) -> Result<Vec<ExecutionResult>, Self::Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MismatchField {
+    Fee,
+    Status,
+    PostBalances,
+    LogMessages,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayMismatch {
+    /// Index of the transaction in the block
+    pub index: usize,
+    pub signature: Signature,
+    pub fields: Vec<MismatchField>,
+    pub expected: ExecutionResult,
+    pub actual: ExecutionResult,
+}
+
+impl Nodes {
+    /// Sanitize transactions of the block with addresses loaded from the recorded metadata,
+    /// `reserved_account_keys` should match features active at the slot of the block
+    pub fn get_replay_transactions(
+        &self,
+        reserved_account_keys: &HashSet<Pubkey>,
+    ) -> Result<Vec<ReplayTransaction>, ReplayError> {
+        let block = self.get_block().ok_or(DecodeError::MissedBlock)?;
+        self.get_block_transactions(block)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, transaction)| {
+                let tx = self.decode_versioned_transaction(transaction)?;
+                let meta = self
+                    .decode_transaction_status_meta(transaction)?
+                    .ok_or(ReplayError::MissedMetadata { index })?;
+                let transaction = SanitizedTransaction::try_create(
+                    tx,
+                    MessageHash::Compute,
+                    None,
+                    SimpleAddressLoader::Enabled(meta.loaded_addresses.clone()),
+                    reserved_account_keys,
+                )
+                .map_err(|error| ReplayError::Sanitize { index, error })?;
+                Ok(ReplayTransaction { transaction, meta })
+            })
+            .collect()
+    }
+}
+
+/// Execute transactions and compare results with the recorded metadata, logs are compared
+/// only if they were recorded
+pub fn replay_block<E: ReplayExecutor>(
+    executor: &mut E,
+    slot: u64,
+    transactions: &[ReplayTransaction],
+) -> Result<Vec<ReplayMismatch>, ReplayError> {
+    let sanitized = transactions
+        .iter()
+        .map(|tx| tx.transaction.clone())
+        .collect::<Vec<_>>();
+    let results = executor
+        .execute_block(slot, &sanitized)
+        .map_err(|error| ReplayError::Executor(error.into()))?;
+    if results.len() != transactions.len() {
+        return Err(ReplayError::InvalidResultCount {
+            expected: transactions.len(),
+            found: results.len(),
+        });
+    }
+
+    Ok(transactions
+        .iter()
+        .zip(results)
+        .enumerate()
+        .filter_map(|(index, (tx, actual))| {
+            let expected = ExecutionResult::from(&tx.meta);
+            let fields = [
+                (MismatchField::Fee, expected.fee != actual.fee),
+                (MismatchField::Status, expected.status != actual.status),
+                (
+                    MismatchField::PostBalances,
+                    expected.post_balances != actual.post_balances,
+                ),
+                (
+                    MismatchField::LogMessages,
+                    expected.log_messages.is_some() && expected.log_messages != actual.log_messages,
+                ),
+            ]
+            .into_iter()
+            .filter_map(|(field, mismatch)| mismatch.then_some(field))
+            .collect::<Vec<_>>();
+            (!fields.is_empty()).then(|| ReplayMismatch {
+                index,
+                signature: *tx.transaction.signature(),
+                fields,
+                expected,
+                actual,
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error("transaction {index} has no metadata")]
+    MissedMetadata { index: usize },
+    #[error("failed to sanitize transaction {index}: {error}")]
+    Sanitize {
+        index: usize,
+        error: TransactionError,
+    },
+    #[error("executor failed: {0}")]
+    Executor(Box<dyn StdError + Send + Sync>),
+    #[error("expected {expected} execution results, found {found}")]
+    InvalidResultCount { expected: usize, found: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            replay::{ExecutionResult, MismatchField, ReplayExecutor, replay_block},
+            solana::tests::create_nodes,
+        },
+        solana_sdk::transaction::SanitizedTransaction,
+        std::{collections::HashSet, convert::Infallible},
+    };
+
+    /// Returns preset results instead of executing transactions
+    struct PresetExecutor(Vec<ExecutionResult>);
+
+    impl ReplayExecutor for PresetExecutor {
+        type Error = Infallible;
+
+        fn execute_block(
+            &mut self,
+            _slot: u64,
+            _transactions: &[SanitizedTransaction],
+        ) -> Result<Vec<ExecutionResult>, Self::Error> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let transactions = create_nodes()
+            .get_replay_transactions(&HashSet::new())
+            .expect("valid transactions");
+        assert_eq!(transactions.len(), 1);
+
+        let recorded = transactions
+            .iter()
+            .map(|tx| ExecutionResult::from(&tx.meta))
+            .collect::<Vec<_>>();
+        let mut executor = PresetExecutor(recorded.clone());
+        assert!(
+            replay_block(&mut executor, 16848004, &transactions)
+                .unwrap()
+                .is_empty()
+        );
+
+        executor.0[0].fee += 1;
+        let mismatches = replay_block(&mut executor, 16848004, &transactions).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].fields, [MismatchField::Fee]);
+        assert_eq!(mismatches[0].expected, recorded[0]);
+    }
+}
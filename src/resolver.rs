@@ -0,0 +1,263 @@
+#[cfg(feature = "source")]
+use crate::{index::CidIndex, source::CarSource};
+use {
+    crate::{
+        cache::NodeCache,
+        node::{
+            DataFrame, DataFrameReader, DataFrames, DecodeMode, Node, NodeError, Nodes,
+            ReassableError,
+        },
+        util,
+    },
+    bytes::Bytes,
+    cid::Cid,
+    std::{borrow::Cow, future::Future, sync::Arc},
+};
+
+/// Lookup of nodes by CID, e.g. to follow `DataFrame::next` links
+pub trait NodeResolver {
+    /// `None` if the node is unknown to the resolver
+    fn resolve(
+        &self,
+        cid: &Cid,
+    ) -> impl Future<Output = Result<Option<Cow<'_, Node>>, NodeError>> + Send;
+
+    /// Cache of reassembled payloads used by `reassemble_payload`
+    fn payload_cache(&self) -> Option<&NodeCache> {
+        None
+    }
+}
+
+impl NodeResolver for Nodes {
+    async fn resolve(&self, cid: &Cid) -> Result<Option<Cow<'_, Node>>, NodeError> {
+        Ok(self.nodes.get(cid).map(Cow::Borrowed))
+    }
+}
+
+impl<T: NodeResolver + Sync> NodeResolver for &T {
+    async fn resolve(&self, cid: &Cid) -> Result<Option<Cow<'_, Node>>, NodeError> {
+        (**self).resolve(cid).await
+    }
+
+    fn payload_cache(&self) -> Option<&NodeCache> {
+        (**self).payload_cache()
+    }
+}
+
+/// Resolve from the first resolver, then from the second one, e.g. nodes of the block and
+/// then the whole file
+impl<A, B> NodeResolver for (A, B)
+where
+    A: NodeResolver + Sync,
+    B: NodeResolver + Sync,
+{
+    async fn resolve(&self, cid: &Cid) -> Result<Option<Cow<'_, Node>>, NodeError> {
+        match self.0.resolve(cid).await? {
+            Some(node) => Ok(Some(node)),
+            None => self.1.resolve(cid).await,
+        }
+    }
+
+    fn payload_cache(&self) -> Option<&NodeCache> {
+        self.0.payload_cache().or_else(|| self.1.payload_cache())
+    }
+}
+
+/// Nodes and payloads of `NodeCache`, only nodes decoded in the same mode are resolved
+#[derive(Debug, Clone, Copy)]
+pub struct CacheResolver<'a> {
+    cache: &'a NodeCache,
+    decode_mode: DecodeMode,
+}
+
+impl<'a> CacheResolver<'a> {
+    pub const fn new(cache: &'a NodeCache) -> Self {
+        Self {
+            cache,
+            decode_mode: DecodeMode::Lenient,
+        }
+    }
+
+    pub const fn decode_mode(mut self, mode: DecodeMode) -> Self {
+        self.decode_mode = mode;
+        self
+    }
+}
+
+impl NodeResolver for CacheResolver<'_> {
+    async fn resolve(&self, cid: &Cid) -> Result<Option<Cow<'_, Node>>, NodeError> {
+        let node = self.cache.get_node(cid, self.decode_mode);
+        Ok(node.map(|node| Cow::Owned(Arc::unwrap_or_clone(node))))
+    }
+
+    fn payload_cache(&self) -> Option<&NodeCache> {
+        Some(self.cache)
+    }
+}
+
+/// Output of a future which resolves nodes from memory only, e.g. with `Nodes`
+pub(crate) fn resolved<F: Future>(future: F) -> F::Output {
+    util::now_or_never(future).expect("nodes are resolved without waiting")
+}
+
+/// Random access reads of nodes located by `CidIndex`
+#[cfg(feature = "source")]
+#[derive(Debug)]
+pub struct IndexResolver<'a, S> {
+    index: &'a CidIndex,
+    source: &'a S,
+    decode_mode: DecodeMode,
+}
+
+#[cfg(feature = "source")]
+impl<'a, S> IndexResolver<'a, S> {
+    pub const fn new(index: &'a CidIndex, source: &'a S) -> Self {
+        Self {
+            index,
+            source,
+            decode_mode: DecodeMode::Lenient,
+        }
+    }
+
+    pub const fn decode_mode(mut self, mode: DecodeMode) -> Self {
+        self.decode_mode = mode;
+        self
+    }
+}
+
+#[cfg(feature = "source")]
+impl<S: CarSource + Sync> NodeResolver for IndexResolver<'_, S> {
+    async fn resolve(&self, cid: &Cid) -> Result<Option<Cow<'_, Node>>, NodeError> {
+        let node = self
+            .index
+            .fetch_node(self.source, cid, self.decode_mode)
+            .await?;
+        Ok(node.map(|node| Cow::Owned(node.node)))
+    }
+}
+
+/// Same as `Nodes::reassemble_dataframes`, but frames are resolved with `resolver`
+pub async fn reassemble_dataframes<'a, R: NodeResolver>(
+    resolver: &'a R,
+    dataframe: &'a DataFrame,
+) -> Result<Vec<u8>, ReassableError> {
+    dataframe_reader(resolver, dataframe).await?.into_vec()
+}
+
+/// Same as `reassemble_dataframes`, but the payload is looked up in and added to the payload
+/// cache of the resolver, if any; payloads of a single frame are not cached
+pub async fn reassemble_payload<R: NodeResolver>(
+    resolver: &R,
+    dataframe: &DataFrame,
+) -> Result<Bytes, ReassableError> {
+    let cache = resolver
+        .payload_cache()
+        .filter(|_cache| !dataframe.next.is_empty())
+        .map(|cache| (cache, dataframe.cid()));
+    if let Some(payload) = cache
+        .as_ref()
+        .and_then(|(cache, cid)| cache.get_payload(cid))
+    {
+        return Ok(payload);
+    }
+
+    let payload = Bytes::from(reassemble_dataframes(resolver, dataframe).await?);
+    if let Some((cache, cid)) = cache {
+        cache.insert_payload(cid, payload.clone());
+    }
+    Ok(payload)
+}
+
+/// Same as `Nodes::dataframe_reader`, but frames are resolved with `resolver`
+pub async fn dataframe_reader<'a, R: NodeResolver>(
+    resolver: &'a R,
+    dataframe: &'a DataFrame,
+) -> Result<DataFrameReader<'a>, ReassableError> {
+    let mut dataframes = DataFrames::new(dataframe)?;
+    while let Some(cid) = dataframes.next_cid() {
+        dataframes.push(cid, resolver.resolve(&cid).await?)?;
+    }
+    dataframes.into_reader()
+}
+
+#[cfg(all(test, feature = "source"))]
+mod tests {
+    use {
+        crate::{
+            index::CidIndex,
+            node::{DataFrame, Node, NodeError, Nodes, ReassableError},
+            resolver::{IndexResolver, NodeResolver, reassemble_dataframes},
+            source::FileSource,
+            util::tests::{TempFile, encode_car, encode_cid, encode_link},
+        },
+        serde_cbor::Value,
+        std::io::Cursor,
+    };
+
+    #[tokio::test]
+    async fn test_index_resolver() {
+        let encode_frame = |index: i128, data: &[u8], next: &[_]| {
+            serde_cbor::to_vec(&Value::Array(vec![
+                Value::Integer(6),
+                Value::Null,
+                Value::Integer(index),
+                Value::Integer(3),
+                Value::Bytes(data.to_vec()),
+                Value::Array(next.iter().map(encode_link).collect()),
+            ]))
+            .unwrap()
+        };
+        let last = encode_frame(2, b"!", &[]);
+        let last_cid = encode_cid(&last);
+        let middle = encode_frame(1, b" world", &[]);
+        let middle_cid = encode_cid(&middle);
+        let car = encode_car(&[(last_cid, last), (middle_cid, middle)]);
+
+        let mut index = CidIndex::build(Cursor::new(&car)).await.expect("valid CAR");
+        let file = TempFile::new("car-resolver", &car);
+        let source = FileSource::new(&file.0);
+
+        // the middle frame is in memory, the last one is read from the file
+        let mut nodes = Nodes::default();
+        nodes.nodes.insert(
+            middle_cid,
+            Node::DataFrame(DataFrame {
+                index: Some(1),
+                total: Some(3),
+                data: b" world".to_vec(),
+                next: vec![],
+                ..Default::default()
+            }),
+        );
+        let first = DataFrame {
+            index: Some(0),
+            total: Some(3),
+            data: b"hello".to_vec(),
+            next: vec![middle_cid, last_cid],
+            ..Default::default()
+        };
+        let resolver = (nodes, IndexResolver::new(&index, &source));
+        assert_eq!(
+            reassemble_dataframes(&resolver, &first).await.unwrap(),
+            b"hello world!"
+        );
+        assert!(matches!(
+            reassemble_dataframes(&resolver.0, &first).await,
+            Err(ReassableError::MissedCid(cid)) if cid == last_cid
+        ));
+
+        // section of another node is not returned for the requested cid
+        let section = *index.get(&last_cid).unwrap();
+        index.sections.insert(middle_cid, section);
+        let resolver = IndexResolver::new(&index, &source);
+        let error = resolver.resolve(&middle_cid).await.unwrap_err();
+        assert!(matches!(
+            error,
+            NodeError::Section { ref error, .. } if matches!(
+                **error,
+                NodeError::UnexpectedCid { ref expected, ref found }
+                    if **expected == middle_cid && **found == last_cid
+            )
+        ));
+    }
+}
@@ -0,0 +1,517 @@
+use {
+    crate::{
+        index::{SignatureIndex, SlotIndex},
+        node::Nodes,
+        solana::DecodeError,
+        source::FileSource,
+    },
+    http_body_util::{BodyExt, Full},
+    hyper::{
+        Method, Request, Response, StatusCode,
+        body::{Bytes, Incoming},
+        header::CONTENT_TYPE,
+        server::conn::http1,
+        service::service_fn,
+    },
+    hyper_util::rt::TokioIo,
+    serde::{Deserialize, de::DeserializeOwned},
+    serde_json::{Value, json},
+    solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature},
+    solana_transaction_status::{
+        BlockEncodingOptions, EncodeError, TransactionDetails, UiTransactionEncoding,
+    },
+    std::{convert::Infallible, io, str::FromStr, sync::Arc},
+    tokio::net::TcpListener,
+};
+
+const MAX_SIGNATURES_FOR_ADDRESS_LIMIT: usize = 1_000;
+
+/// JSON-RPC handler over indexed CAR file
+pub struct RpcServer {
+    car: FileSource,
+    slots: SlotIndex,
+    signatures: SignatureIndex,
+}
+
+impl RpcServer {
+    pub const fn new(car: FileSource, slots: SlotIndex, signatures: SignatureIndex) -> Self {
+        Self {
+            car,
+            slots,
+            signatures,
+        }
+    }
+
+    /// Accept connections until the listener fails, errors of single connections are passed
+    /// to `on_error`
+    pub async fn serve<F>(self: Arc<Self>, listener: TcpListener, on_error: F) -> io::Result<()>
+    where
+        F: Fn(hyper::Error) + Clone + Send + 'static,
+    {
+        loop {
+            let (stream, _addr) = listener.accept().await?;
+            let server = Arc::clone(&self);
+            let on_error = on_error.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req| handle_request(Arc::clone(&server), req));
+                if let Err(error) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    on_error(error);
+                }
+            });
+        }
+    }
+
+    async fn read_block(&self, slot: u64) -> Result<Option<Nodes>, RpcError> {
+        self.slots
+            .fetch_block(&self.car, slot)
+            .await
+            .map_err(RpcError::internal)
+    }
+
+    /// `None` for the first block, its parent is stored in the previous epoch
+    async fn get_previous_blockhash(&self, parent_slot: u64) -> Result<Option<Hash>, RpcError> {
+        self.slots
+            .fetch_blockhash(&self.car, parent_slot)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn process(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "getBlock" => self.get_block(params).await,
+            "getTransaction" => self.get_transaction(params).await,
+            "getSignaturesForAddress" => self.get_signatures_for_address(params),
+            "getBlockTime" => self.get_block_time(params),
+            "getFirstAvailableBlock" => match self.slots.first_slot() {
+                Some(slot) => Ok(json!(slot)),
+                None => Err(RpcError::new(
+                    -32004,
+                    "Block not available: no blocks are indexed",
+                )),
+            },
+            _ => Err(RpcError::new(-32601, "Method not found")),
+        }
+    }
+
+    async fn get_block(&self, params: Value) -> Result<Value, RpcError> {
+        let (slot, config) = parse_params::<u64, EncodingConfig<BlockConfig>>(params)?;
+        let config = config.map(EncodingConfig::into_config).unwrap_or_default();
+
+        let Some(nodes) = self.read_block(slot).await? else {
+            return Err(RpcError::slot_skipped(slot));
+        };
+        let block = nodes.get_block().ok_or(DecodeError::MissedBlock)?;
+        let previous_blockhash = self.get_previous_blockhash(block.meta.parent_slot).await?;
+
+        let block = nodes.encode_block(
+            previous_blockhash.unwrap_or_default(),
+            config.encoding.unwrap_or(UiTransactionEncoding::Json),
+            BlockEncodingOptions {
+                transaction_details: config
+                    .transaction_details
+                    .unwrap_or(TransactionDetails::Full),
+                show_rewards: config.rewards.unwrap_or(true),
+                max_supported_transaction_version: config.max_supported_transaction_version,
+            },
+        )?;
+        let mut block = serde_json::to_value(block).map_err(RpcError::internal)?;
+        if previous_blockhash.is_none() {
+            block["previousBlockhash"] = Value::Null;
+        }
+        Ok(block)
+    }
+
+    async fn get_transaction(&self, params: Value) -> Result<Value, RpcError> {
+        let (signature, config) =
+            parse_params::<String, EncodingConfig<TransactionConfig>>(params)?;
+        let signature = parse_signature(&signature)?;
+        let config = config.map(EncodingConfig::into_config).unwrap_or_default();
+
+        let Some(tx) = self.signatures.get(&signature) else {
+            return Ok(Value::Null);
+        };
+        let Some(nodes) = self.read_block(tx.slot).await? else {
+            return Ok(Value::Null);
+        };
+
+        let tx = nodes.encode_transaction(
+            &signature,
+            config.encoding.unwrap_or(UiTransactionEncoding::Json),
+            config.max_supported_transaction_version,
+        )?;
+        serde_json::to_value(tx).map_err(RpcError::internal)
+    }
+
+    fn get_signatures_for_address(&self, params: Value) -> Result<Value, RpcError> {
+        let (address, config) = parse_params::<String, SignaturesForAddressConfig>(params)?;
+        let address = Pubkey::from_str(&address)
+            .map_err(|_| RpcError::invalid_params("Invalid param: Invalid"))?;
+        let config = config.unwrap_or_default();
+        let limit = config.limit.unwrap_or(MAX_SIGNATURES_FOR_ADDRESS_LIMIT);
+        if limit == 0 || limit > MAX_SIGNATURES_FOR_ADDRESS_LIMIT {
+            return Err(RpcError::invalid_params(format!(
+                "Invalid limit; max {MAX_SIGNATURES_FOR_ADDRESS_LIMIT}"
+            )));
+        }
+        let before = config.before.as_deref().map(parse_signature).transpose()?;
+        let until = config.until.as_deref().map(parse_signature).transpose()?;
+
+        let Some(signatures) = self.signatures.get_signatures_for_address(&address) else {
+            return Err(RpcError::new(
+                -32011,
+                "Transaction history is not available from this node, address index is disabled",
+            ));
+        };
+        let mut signatures = signatures.peekable();
+        if let Some(before) = before {
+            while signatures
+                .next_if(|signature| **signature != before)
+                .is_some()
+            {}
+            signatures.next();
+        }
+
+        let mut result = vec![];
+        for signature in signatures
+            .take_while(|signature| Some(**signature) != until)
+            .take(limit)
+        {
+            let Some(tx) = self.signatures.get(signature) else {
+                continue;
+            };
+            result.push(json!({
+                "signature": signature.to_string(),
+                "slot": tx.slot,
+                "err": tx.err,
+                "memo": null,
+                "blockTime": self.get_block_time_value(tx.slot),
+                "confirmationStatus": "finalized",
+            }));
+        }
+        Ok(Value::Array(result))
+    }
+
+    fn get_block_time(&self, params: Value) -> Result<Value, RpcError> {
+        let (slot, _config) = parse_params::<u64, Value>(params)?;
+        match self.slots.get(slot) {
+            Some(_) => Ok(self.get_block_time_value(slot)),
+            None => Err(RpcError::new(
+                -32004,
+                format!("Block not available for slot {slot}"),
+            )),
+        }
+    }
+
+    fn get_block_time_value(&self, slot: u64) -> Value {
+        match self.slots.get(slot).map(|block| block.blocktime) {
+            Some(0) | None => Value::Null,
+            Some(blocktime) => json!(blocktime),
+        }
+    }
+}
+
+async fn handle_request(
+    state: Arc<RpcServer>,
+    req: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    if req.method() != Method::POST {
+        return Ok(Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .body(Full::default())
+            .expect("valid response"));
+    }
+
+    let body = match req.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(error) => {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Full::new(Bytes::from(error.to_string())))
+                .expect("valid response"));
+        }
+    };
+
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in requests {
+                responses.push(process_request(&state, request).await);
+            }
+            Value::Array(responses)
+        }
+        Ok(request) => process_request(&state, request).await,
+        Err(_) => RpcError::new(-32700, "Parse error").into_response(Value::Null),
+    };
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(response.to_string())))
+        .expect("valid response"))
+}
+
+async fn process_request(state: &RpcServer, request: Value) -> Value {
+    #[derive(Deserialize)]
+    struct JsonRpcRequest {
+        jsonrpc: String,
+        id: Value,
+        method: String,
+        #[serde(default)]
+        params: Value,
+    }
+
+    let request = match serde_json::from_value::<JsonRpcRequest>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        _ => return RpcError::new(-32600, "Invalid request").into_response(Value::Null),
+    };
+
+    match state.process(&request.method, request.params).await {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": request.id }),
+        Err(error) => error.into_response(request.id),
+    }
+}
+
+fn parse_params<T: DeserializeOwned, C: DeserializeOwned>(
+    params: Value,
+) -> Result<(T, Option<C>), RpcError> {
+    let mut params = match params {
+        Value::Array(params) => params.into_iter(),
+        _ => return Err(RpcError::invalid_params("`params` should be an array")),
+    };
+    let value = params
+        .next()
+        .ok_or_else(|| RpcError::invalid_params("missing first parameter"))?;
+    let value = serde_json::from_value(value).map_err(RpcError::invalid_params)?;
+    let config = match params.next() {
+        Some(Value::Null) | None => None,
+        Some(config) => Some(serde_json::from_value(config).map_err(RpcError::invalid_params)?),
+    };
+    Ok((value, config))
+}
+
+fn parse_signature(signature: &str) -> Result<Signature, RpcError> {
+    Signature::from_str(signature)
+        .map_err(|_| RpcError::invalid_params("Invalid param: invalid signature"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EncodingConfig<T> {
+    Deprecated(UiTransactionEncoding),
+    Current(T),
+}
+
+impl<T: From<UiTransactionEncoding>> EncodingConfig<T> {
+    fn into_config(self) -> T {
+        match self {
+            Self::Deprecated(encoding) => T::from(encoding),
+            Self::Current(config) => config,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockConfig {
+    encoding: Option<UiTransactionEncoding>,
+    transaction_details: Option<TransactionDetails>,
+    rewards: Option<bool>,
+    max_supported_transaction_version: Option<u8>,
+}
+
+impl From<UiTransactionEncoding> for BlockConfig {
+    fn from(encoding: UiTransactionEncoding) -> Self {
+        Self {
+            encoding: Some(encoding),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionConfig {
+    encoding: Option<UiTransactionEncoding>,
+    max_supported_transaction_version: Option<u8>,
+}
+
+impl From<UiTransactionEncoding> for TransactionConfig {
+    fn from(encoding: UiTransactionEncoding) -> Self {
+        Self {
+            encoding: Some(encoding),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignaturesForAddressConfig {
+    limit: Option<usize>,
+    before: Option<String>,
+    until: Option<String>,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_params(message: impl ToString) -> Self {
+        Self::new(-32602, message.to_string())
+    }
+
+    fn internal(error: impl ToString) -> Self {
+        Self::new(-32603, error.to_string())
+    }
+
+    fn slot_skipped(slot: u64) -> Self {
+        Self::new(
+            -32009,
+            format!("Slot {slot} was skipped, or missing in long-term storage"),
+        )
+    }
+
+    fn into_response(self, id: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "error": { "code": self.code, "message": self.message },
+            "id": id,
+        })
+    }
+}
+
+impl From<DecodeError> for RpcError {
+    fn from(error: DecodeError) -> Self {
+        match error {
+            DecodeError::Encode(EncodeError::UnsupportedTransactionVersion(version)) => Self::new(
+                -32015,
+                format!(
+                    "Transaction version ({version}) is not supported by the requesting client. \
+                    Please try the request again with the following configuration parameter: \
+                    \"maxSupportedTransactionVersion\": {version}"
+                ),
+            ),
+            DecodeError::UnsupportedEncoding(encoding) => {
+                Self::invalid_params(format!("Unsupported encoding: {encoding:?}"))
+            }
+            error => Self::internal(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            index::{SignatureIndex, SlotIndex},
+            rpc::RpcServer,
+            solana::tests::create_car,
+            source::FileSource,
+            util::tests::TempFile,
+        },
+        serde_json::{Value, json},
+        std::{io::Cursor, net::SocketAddr, sync::Arc},
+        tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::{TcpListener, TcpStream},
+        },
+    };
+
+    async fn call(addr: SocketAddr, method: &str, params: Value) -> Value {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let body = body.to_string();
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "POST / HTTP/1.1\r\nhost: {addr}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (_head, body) = response.split_once("\r\n\r\n").expect("HTTP response");
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_server() {
+        let car = create_car();
+        let file = TempFile::new("rpc-server", &car);
+        let mut signatures = SignatureIndex::new(true);
+        let slots = SlotIndex::build(Cursor::new(&car), |_block, nodes| {
+            signatures.push_block(nodes)
+        })
+        .await
+        .expect("valid CAR");
+        let blockhash = slots
+            .fetch_blockhash(&FileSource::new(&file.0), 16848004)
+            .await
+            .expect("valid entry");
+        assert_eq!(
+            blockhash.map(|hash| hash.to_string()).as_deref(),
+            Some("A8jD8bJVZUALsEjK654LW3NX5Pidt8GCevHydMNzXvN3")
+        );
+        let server = RpcServer::new(FileSource::new(&file.0), slots, signatures);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Arc::new(server).serve(listener, |error| panic!("{error}")));
+
+        let signature = "3hLwbGQetenDLR5sq3j1gemN2u8TA12knQTDa8NZoDC4tBrdSX22yEP8noNTEFjPHGSmpQAyqt97V3BXsMX1o3fu";
+        let block = call(addr, "getBlock", json!([16848004])).await;
+        let block = &block["result"];
+        assert_eq!(
+            block["blockhash"],
+            "A8jD8bJVZUALsEjK654LW3NX5Pidt8GCevHydMNzXvN3"
+        );
+        // the parent block is not in the file
+        assert_eq!(block["previousBlockhash"], Value::Null);
+        assert_eq!(
+            block["transactions"][0]["transaction"]["signatures"][0],
+            signature
+        );
+
+        let tx = call(addr, "getTransaction", json!([signature])).await;
+        assert_eq!(tx["result"]["slot"], 16848004);
+        assert_eq!(tx["result"]["meta"]["err"], Value::Null);
+
+        let signatures = call(
+            addr,
+            "getSignaturesForAddress",
+            json!(["Vote111111111111111111111111111111111111111"]),
+        )
+        .await;
+        assert_eq!(signatures["result"][0]["signature"], signature);
+        assert_eq!(signatures["result"][0]["slot"], 16848004);
+
+        let skipped = call(addr, "getBlock", json!([16848003])).await;
+        assert_eq!(skipped["error"]["code"], -32009);
+
+        let first = call(addr, "getFirstAvailableBlock", json!([])).await;
+        assert_eq!(first["result"], 16848004);
+        let empty = RpcServer::new(
+            FileSource::new(&file.0),
+            SlotIndex::default(),
+            SignatureIndex::new(false),
+        );
+        assert!(matches!(
+            empty.process("getFirstAvailableBlock", Value::Null).await,
+            Err(error) if error.code == -32004
+        ));
+    }
+}
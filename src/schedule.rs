@@ -0,0 +1,232 @@
+use {
+    std::{num::NonZeroU64, ops::RangeInclusive},
+    thiserror::Error,
+};
+
+/// Length of the first epoch with warmup, same as in Solana
+pub const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+/// Slots per epoch on mainnet-beta
+pub const DEFAULT_SLOTS_PER_EPOCH: u64 = 432_000;
+
+/// Mapping between slots and epochs, same rules as `solana_sdk::epoch_schedule::EpochSchedule`:
+/// with warmup epochs start at `MINIMUM_SLOTS_PER_EPOCH` slots and double until they reach
+/// `slots_per_epoch`, arithmetic saturates for epochs and slots out of range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EpochSchedule {
+    /// Never zero
+    slots_per_epoch: u64,
+    warmup: bool,
+    first_normal_epoch: u64,
+    first_normal_slot: u64,
+}
+
+impl Default for EpochSchedule {
+    fn default() -> Self {
+        Self::without_warmup(NonZeroU64::new(DEFAULT_SLOTS_PER_EPOCH).unwrap())
+    }
+}
+
+impl EpochSchedule {
+    pub const fn without_warmup(slots_per_epoch: NonZeroU64) -> Self {
+        Self::new(slots_per_epoch, false)
+    }
+
+    pub const fn new(slots_per_epoch: NonZeroU64, warmup: bool) -> Self {
+        let slots_per_epoch = slots_per_epoch.get();
+        let (first_normal_epoch, first_normal_slot) = if warmup {
+            let next_power_of_two = slots_per_epoch.next_power_of_two();
+            let log2_slots_per_epoch = next_power_of_two
+                .trailing_zeros()
+                .saturating_sub(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros());
+            (
+                log2_slots_per_epoch as u64,
+                next_power_of_two.saturating_sub(MINIMUM_SLOTS_PER_EPOCH),
+            )
+        } else {
+            (0, 0)
+        };
+        Self {
+            slots_per_epoch,
+            warmup,
+            first_normal_epoch,
+            first_normal_slot,
+        }
+    }
+
+    pub const fn slots_per_epoch(&self) -> u64 {
+        self.slots_per_epoch
+    }
+
+    pub const fn warmup(&self) -> bool {
+        self.warmup
+    }
+
+    pub const fn first_normal_epoch(&self) -> u64 {
+        self.first_normal_epoch
+    }
+
+    pub const fn first_normal_slot(&self) -> u64 {
+        self.first_normal_slot
+    }
+
+    pub const fn get_slots_in_epoch(&self, epoch: u64) -> u64 {
+        if epoch < self.first_normal_epoch {
+            saturating_pow2(epoch.saturating_add(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros() as u64))
+        } else {
+            self.slots_per_epoch
+        }
+    }
+
+    pub const fn get_epoch(&self, slot: u64) -> u64 {
+        self.get_epoch_and_slot_index(slot).0
+    }
+
+    pub const fn get_epoch_and_slot_index(&self, slot: u64) -> (u64, u64) {
+        if slot < self.first_normal_slot {
+            let epoch = (slot + MINIMUM_SLOTS_PER_EPOCH + 1)
+                .next_power_of_two()
+                .trailing_zeros()
+                .saturating_sub(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros())
+                .saturating_sub(1) as u64;
+            let epoch_len =
+                saturating_pow2(epoch + MINIMUM_SLOTS_PER_EPOCH.trailing_zeros() as u64);
+            (epoch, slot - (epoch_len - MINIMUM_SLOTS_PER_EPOCH))
+        } else {
+            let normal_slot_index = slot - self.first_normal_slot;
+            (
+                self.first_normal_epoch + normal_slot_index / self.slots_per_epoch,
+                normal_slot_index % self.slots_per_epoch,
+            )
+        }
+    }
+
+    pub const fn get_first_slot_in_epoch(&self, epoch: u64) -> u64 {
+        if epoch <= self.first_normal_epoch {
+            saturating_pow2(epoch)
+                .saturating_sub(1)
+                .saturating_mul(MINIMUM_SLOTS_PER_EPOCH)
+        } else {
+            epoch
+                .saturating_sub(self.first_normal_epoch)
+                .saturating_mul(self.slots_per_epoch)
+                .saturating_add(self.first_normal_slot)
+        }
+    }
+
+    pub const fn get_last_slot_in_epoch(&self, epoch: u64) -> u64 {
+        self.get_first_slot_in_epoch(epoch)
+            .saturating_add(self.get_slots_in_epoch(epoch))
+            .saturating_sub(1)
+    }
+
+    pub const fn get_slot_range(&self, epoch: u64) -> RangeInclusive<u64> {
+        self.get_first_slot_in_epoch(epoch)..=self.get_last_slot_in_epoch(epoch)
+    }
+}
+
+/// `2^exp`, `u64::MAX` if it does not fit
+const fn saturating_pow2(exp: u64) -> u64 {
+    match 1u64.checked_shl(if exp > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        exp as u32
+    }) {
+        Some(value) => value,
+        None => u64::MAX,
+    }
+}
+
+/// Warmup epochs are derived from `slots_per_epoch`, as done by Solana
+#[cfg(feature = "solana")]
+impl TryFrom<solana_sdk::epoch_schedule::EpochSchedule> for EpochSchedule {
+    type Error = ScheduleError;
+
+    fn try_from(schedule: solana_sdk::epoch_schedule::EpochSchedule) -> Result<Self, Self::Error> {
+        let slots_per_epoch =
+            NonZeroU64::new(schedule.slots_per_epoch).ok_or(ScheduleError::ZeroSlotsPerEpoch)?;
+        Ok(Self::new(slots_per_epoch, schedule.warmup))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ScheduleError {
+    #[error("slots per epoch should not be zero")]
+    ZeroSlotsPerEpoch,
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solana")]
+    use crate::schedule::ScheduleError;
+    use {
+        crate::schedule::{DEFAULT_SLOTS_PER_EPOCH, EpochSchedule},
+        std::num::NonZeroU64,
+    };
+
+    #[test]
+    fn test_schedule() {
+        let mainnet = EpochSchedule::default();
+        assert_eq!(
+            mainnet,
+            EpochSchedule::without_warmup(NonZeroU64::new(DEFAULT_SLOTS_PER_EPOCH).unwrap())
+        );
+        assert_eq!(mainnet.get_epoch(0), 0);
+        assert_eq!(mainnet.get_epoch(431_999), 0);
+        assert_eq!(mainnet.get_epoch(16_848_004), 39);
+        assert_eq!(mainnet.get_first_slot_in_epoch(39), 16_848_000);
+        assert_eq!(mainnet.get_slot_range(1), 432_000..=863_999);
+
+        // 32, 64, ..., 4096 slots, then 8192 slots per epoch
+        let schedule = EpochSchedule::new(NonZeroU64::new(8192).unwrap(), true);
+        assert_eq!(schedule.first_normal_epoch(), 8);
+        assert_eq!(schedule.first_normal_slot(), 8160);
+        assert_eq!(schedule.get_epoch_and_slot_index(0), (0, 0));
+        assert_eq!(schedule.get_epoch_and_slot_index(31), (0, 31));
+        assert_eq!(schedule.get_epoch_and_slot_index(32), (1, 0));
+        assert_eq!(schedule.get_epoch_and_slot_index(95), (1, 63));
+        assert_eq!(schedule.get_epoch_and_slot_index(96), (2, 0));
+        assert_eq!(schedule.get_epoch_and_slot_index(8160), (8, 0));
+        assert_eq!(schedule.get_epoch_and_slot_index(8160 + 8192), (9, 0));
+        for epoch in 0..12 {
+            let first = schedule.get_first_slot_in_epoch(epoch);
+            assert_eq!(schedule.get_epoch_and_slot_index(first), (epoch, 0));
+            let last = schedule.get_last_slot_in_epoch(epoch);
+            assert_eq!(
+                schedule.get_epoch_and_slot_index(last),
+                (epoch, schedule.get_slots_in_epoch(epoch) - 1)
+            );
+        }
+
+        // epochs read from untrusted files saturate instead of overflowing
+        for schedule in [mainnet, schedule] {
+            assert_eq!(schedule.get_first_slot_in_epoch(u64::MAX), u64::MAX);
+            assert_eq!(schedule.get_last_slot_in_epoch(u64::MAX), u64::MAX - 1);
+        }
+
+        #[cfg(feature = "solana")]
+        for (slots_per_epoch, warmup) in [(DEFAULT_SLOTS_PER_EPOCH, false), (8192, true)] {
+            let solana = solana_sdk::epoch_schedule::EpochSchedule::custom(
+                slots_per_epoch,
+                slots_per_epoch,
+                warmup,
+            );
+            let schedule = EpochSchedule::try_from(solana.clone()).unwrap();
+            let slots_per_epoch = NonZeroU64::new(slots_per_epoch).unwrap();
+            assert_eq!(schedule, EpochSchedule::new(slots_per_epoch, warmup));
+            for slot in [0, 31, 32, 1000, 8159, 8160, 1_000_000] {
+                assert_eq!(
+                    schedule.get_epoch_and_slot_index(slot),
+                    solana.get_epoch_and_slot_index(slot)
+                );
+            }
+        }
+        #[cfg(feature = "solana")]
+        assert_eq!(
+            EpochSchedule::try_from(solana_sdk::epoch_schedule::EpochSchedule {
+                slots_per_epoch: 0,
+                ..Default::default()
+            }),
+            Err(ScheduleError::ZeroSlotsPerEpoch)
+        );
+    }
+}
@@ -0,0 +1,874 @@
+use {
+    crate::{
+        node::{
+            Block, DataFrame, DataFrameReader, Entry, Kind, MULTIHASH_IDENTITY, Node, NodeError,
+            Nodes, ReassableError, Rewards, Transaction,
+        },
+        resolver::{self, NodeResolver},
+    },
+    bincode::Options,
+    cid::Cid,
+    prost::Message,
+    solana_sdk::{
+        clock::UnixTimestamp, hash::Hash, signature::Signature, transaction::VersionedTransaction,
+    },
+    solana_storage_proto::{
+        StoredExtendedRewards, StoredTransactionStatusMeta, convert::generated,
+    },
+    solana_transaction_status::{
+        BlockEncodingOptions, ConfirmedBlock, ConfirmedTransactionWithStatusMeta, EncodeError,
+        EncodedConfirmedTransactionWithStatusMeta, Reward, RewardType, TransactionStatusMeta,
+        TransactionWithStatusMeta, UiConfirmedBlock, UiTransactionEncoding,
+        VersionedTransactionWithStatusMeta,
+    },
+    std::{
+        borrow::Cow,
+        collections::HashMap,
+        io::{self, Read},
+    },
+    thiserror::Error,
+};
+
+/// `StoredConfirmedBlockReward` of `solana-storage-bigtable`, written before `post_balance`
+type LegacyRewards = Vec<(String, i64)>;
+
+impl Nodes {
+    pub fn get_block(&self) -> Option<&Block> {
+        self.nodes.values().rev().find_map(|node| match node {
+            Node::Block(block) => Some(block),
+            _ => None,
+        })
+    }
+
+    pub fn get_entry(&self, cid: &Cid) -> Result<&Entry, DecodeError> {
+        match self.get_node(cid)? {
+            Node::Entry(entry) => Ok(entry),
+            node => Err(DecodeError::invalid_node(node, Kind::Entry)),
+        }
+    }
+
+    pub fn get_transaction(&self, cid: &Cid) -> Result<&Transaction, DecodeError> {
+        match self.get_node(cid)? {
+            Node::Transaction(transaction) => Ok(transaction),
+            node => Err(DecodeError::invalid_node(node, Kind::Transaction)),
+        }
+    }
+
+    pub fn get_rewards(&self, cid: &Cid) -> Result<Option<&Rewards>, DecodeError> {
+        match self.nodes.get(cid) {
+            Some(Node::Rewards(rewards)) => Ok(Some(rewards)),
+            Some(node) => Err(DecodeError::invalid_node(node, Kind::Rewards)),
+            // empty blocks link to an identity CID that is never written to the CAR
+            None if cid.hash().code() == MULTIHASH_IDENTITY => Ok(None),
+            None => Err(DecodeError::MissedCid(*cid)),
+        }
+    }
+
+    fn get_node(&self, cid: &Cid) -> Result<&Node, DecodeError> {
+        self.nodes.get(cid).ok_or(DecodeError::MissedCid(*cid))
+    }
+
+    /// Returns transactions of the block in the order defined by `Block::entries`
+    pub fn get_block_transactions(&self, block: &Block) -> Result<Vec<&Transaction>, DecodeError> {
+        let mut transactions = vec![];
+        for cid in block.entries.iter() {
+            for cid in self.get_entry(cid)?.transactions.iter() {
+                transactions.push(self.get_transaction(cid)?);
+            }
+        }
+        Ok(transactions)
+    }
+
+    /// Entry with transactions in the order of `Entry::transactions`
+    pub fn decode_entry(&self, entry: &Entry) -> Result<solana_entry::entry::Entry, DecodeError> {
+        Ok(solana_entry::entry::Entry {
+            num_hashes: entry.num_hashes,
+            hash: get_hash(&entry.hash)?,
+            transactions: entry
+                .transactions
+                .iter()
+                .map(|cid| self.decode_versioned_transaction(self.get_transaction(cid)?))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Entries of the block in the order defined by `Block::entries`
+    pub fn decode_block_entries(
+        &self,
+        block: &Block,
+    ) -> Result<Vec<solana_entry::entry::Entry>, DecodeError> {
+        block
+            .entries
+            .iter()
+            .map(|cid| self.decode_entry(self.get_entry(cid)?))
+            .collect()
+    }
+
+    /// Blockhash is the hash of the last entry in the block
+    pub fn get_blockhash(&self, block: &Block) -> Result<Hash, DecodeError> {
+        match block.entries.last() {
+            Some(cid) => get_hash(&self.get_entry(cid)?.hash),
+            None => Err(DecodeError::MissedEntries(block.slot)),
+        }
+    }
+
+    pub fn decode_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<TransactionWithStatusMeta, DecodeError> {
+        resolver::resolved(transaction.decode_payload(self))
+    }
+
+    pub fn decode_versioned_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<VersionedTransaction, DecodeError> {
+        resolver::resolved(transaction.decode_versioned(self))
+    }
+
+    pub fn decode_transaction_status_meta(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Option<TransactionStatusMeta>, DecodeError> {
+        resolver::resolved(transaction.decode_status_meta(self))
+    }
+
+    /// Reassembled and decompressed metadata, `None` if transaction has no metadata
+    pub(crate) fn decompress_transaction_status_meta(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Option<Vec<u8>>, DecodeError> {
+        resolver::resolved(transaction.decompress_status_meta(self))
+    }
+
+    pub(crate) fn build_transaction_with_status_meta(
+        transaction: VersionedTransaction,
+        meta: Option<TransactionStatusMeta>,
+    ) -> Result<TransactionWithStatusMeta, DecodeError> {
+        match meta {
+            Some(meta) => Ok(TransactionWithStatusMeta::Complete(
+                VersionedTransactionWithStatusMeta { transaction, meta },
+            )),
+            None => transaction
+                .into_legacy_transaction()
+                .map(TransactionWithStatusMeta::MissingMetadata)
+                .ok_or(DecodeError::MissedMetadata),
+        }
+    }
+
+    pub fn decode_rewards(
+        &self,
+        rewards: &Rewards,
+    ) -> Result<(Vec<Reward>, Option<u64>), DecodeError> {
+        resolver::resolved(rewards.decode_payload(self))
+    }
+
+    /// Assemble `ConfirmedBlock` from the nodes of a single block, as read by
+    /// `Nodes::read_until_block`
+    pub fn get_confirmed_block(
+        &self,
+        previous_blockhash: Hash,
+    ) -> Result<ConfirmedBlock, DecodeError> {
+        let block = self.get_block().ok_or(DecodeError::MissedBlock)?;
+
+        let blockhash = self.get_blockhash(block)?;
+
+        let transactions = self
+            .get_block_transactions(block)?
+            .into_iter()
+            .map(|transaction| self.decode_transaction(transaction))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (rewards, num_partitions) = match self.get_rewards(&block.rewards)? {
+            Some(rewards) => self.decode_rewards(rewards)?,
+            None => (vec![], None),
+        };
+
+        Ok(ConfirmedBlock {
+            previous_blockhash: previous_blockhash.to_string(),
+            blockhash: blockhash.to_string(),
+            parent_slot: block.meta.parent_slot,
+            transactions,
+            rewards,
+            num_partitions,
+            block_time: get_block_time(block),
+            block_height: block.meta.block_height,
+        })
+    }
+
+    /// Encode block in the same way as `getBlock` RPC method
+    pub fn encode_block(
+        &self,
+        previous_blockhash: Hash,
+        encoding: UiTransactionEncoding,
+        options: BlockEncodingOptions,
+    ) -> Result<UiConfirmedBlock, DecodeError> {
+        check_encoding(encoding)?;
+        self.get_confirmed_block(previous_blockhash)?
+            .encode_with_options(encoding, options)
+            .map_err(Into::into)
+    }
+
+    /// Find transaction in the block by the first signature
+    pub fn get_confirmed_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>, DecodeError> {
+        let block = self.get_block().ok_or(DecodeError::MissedBlock)?;
+        for transaction in self.get_block_transactions(block)? {
+            let tx = self.decode_versioned_transaction(transaction)?;
+            if tx.signatures.first() == Some(signature) {
+                let meta = self.decode_transaction_status_meta(transaction)?;
+                return Ok(Some(ConfirmedTransactionWithStatusMeta {
+                    slot: block.slot,
+                    tx_with_meta: Self::build_transaction_with_status_meta(tx, meta)?,
+                    block_time: get_block_time(block),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Encode transaction in the same way as `getTransaction` RPC method
+    pub fn encode_transaction(
+        &self,
+        signature: &Signature,
+        encoding: UiTransactionEncoding,
+        max_supported_transaction_version: Option<u8>,
+    ) -> Result<Option<EncodedConfirmedTransactionWithStatusMeta>, DecodeError> {
+        check_encoding(encoding)?;
+        self.get_confirmed_transaction(signature)?
+            .map(|tx| tx.encode(encoding, max_supported_transaction_version))
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+/// Blockhash of the block and of its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHashes {
+    pub slot: u64,
+    pub blockhash: Hash,
+    /// `None` if the parent block was not pushed right before the block
+    pub previous_blockhash: Option<Hash>,
+}
+
+/// Tracks blockhash of the last block while blocks are read in the archive order
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockhashTracker {
+    previous: Option<(u64, Hash)>,
+}
+
+impl BlockhashTracker {
+    pub const fn new() -> Self {
+        Self { previous: None }
+    }
+
+    /// Start after a known block, e.g. the last block of the previous epoch
+    pub const fn with_previous(slot: u64, blockhash: Hash) -> Self {
+        Self {
+            previous: Some((slot, blockhash)),
+        }
+    }
+
+    /// Slot and blockhash of the last pushed block
+    pub const fn previous(&self) -> Option<(u64, Hash)> {
+        self.previous
+    }
+
+    /// Push nodes of the next block, as read by `Nodes::read_until_block`
+    pub fn push(&mut self, nodes: &Nodes) -> Result<BlockHashes, DecodeError> {
+        let block = nodes.get_block().ok_or(DecodeError::MissedBlock)?;
+        let blockhash = nodes.get_blockhash(block)?;
+        let previous_blockhash = self
+            .previous
+            .filter(|(slot, _)| *slot == block.meta.parent_slot)
+            .map(|(_, blockhash)| blockhash);
+        self.previous = Some((block.slot, blockhash));
+        Ok(BlockHashes {
+            slot: block.slot,
+            blockhash,
+            previous_blockhash,
+        })
+    }
+
+    /// Push the block and assemble `ConfirmedBlock`, unknown previous blockhash is the default hash
+    pub fn get_confirmed_block(&mut self, nodes: &Nodes) -> Result<ConfirmedBlock, DecodeError> {
+        let hashes = self.push(nodes)?;
+        nodes.get_confirmed_block(hashes.previous_blockhash.unwrap_or_default())
+    }
+}
+
+/// Transaction node resolved with `resolver`, e.g. read from the CAR file with `IndexResolver`
+pub async fn get_transaction<'a, R: NodeResolver>(
+    resolver: &'a R,
+    cid: &Cid,
+) -> Result<Cow<'a, Transaction>, DecodeError> {
+    match resolver.resolve(cid).await? {
+        Some(Cow::Borrowed(Node::Transaction(transaction))) => Ok(Cow::Borrowed(transaction)),
+        Some(Cow::Owned(Node::Transaction(transaction))) => Ok(Cow::Owned(transaction)),
+        Some(node) => Err(DecodeError::invalid_node(&node, Kind::Transaction)),
+        None => Err(DecodeError::MissedCid(*cid)),
+    }
+}
+
+impl Transaction {
+    /// Transaction with metadata, data frames are resolved with `resolver`
+    pub async fn decode_payload<R: NodeResolver>(
+        &self,
+        resolver: &R,
+    ) -> Result<TransactionWithStatusMeta, DecodeError> {
+        let tx = self.decode_versioned(resolver).await?;
+        Nodes::build_transaction_with_status_meta(tx, self.decode_status_meta(resolver).await?)
+    }
+
+    pub async fn decode_versioned<R: NodeResolver>(
+        &self,
+        resolver: &R,
+    ) -> Result<VersionedTransaction, DecodeError> {
+        let buffer = resolver::reassemble_payload(resolver, &self.data).await?;
+        bincode::deserialize::<VersionedTransaction>(&buffer)
+            .map_err(|error| DecodeError::Bincode("transaction", error))
+    }
+
+    pub async fn decode_status_meta<R: NodeResolver>(
+        &self,
+        resolver: &R,
+    ) -> Result<Option<TransactionStatusMeta>, DecodeError> {
+        self.decompress_status_meta(resolver)
+            .await?
+            .map(|buffer| parse_transaction_status_meta(&buffer))
+            .transpose()
+    }
+
+    /// Reassembled and decompressed metadata, `None` if transaction has no metadata
+    pub(crate) async fn decompress_status_meta<R: NodeResolver>(
+        &self,
+        resolver: &R,
+    ) -> Result<Option<Vec<u8>>, DecodeError> {
+        decompress_dataframes("transaction metadata", resolver, &self.metadata).await
+    }
+}
+
+impl Rewards {
+    /// Rewards with the number of partitions (partitioned epoch rewards only), data frames are
+    /// resolved with `resolver`
+    pub async fn decode_payload<R: NodeResolver>(
+        &self,
+        resolver: &R,
+    ) -> Result<(Vec<Reward>, Option<u64>), DecodeError> {
+        match decompress_dataframes("rewards", resolver, &self.data).await? {
+            Some(buffer) => parse_rewards(&buffer),
+            None => Ok((vec![], None)),
+        }
+    }
+}
+
+/// Decompressed rewards in protobuf or one of the bincode layouts
+pub fn parse_rewards(buffer: &[u8]) -> Result<(Vec<Reward>, Option<u64>), DecodeError> {
+    if let Ok(rewards) = generated::Rewards::decode(buffer) {
+        return Ok(rewards.into());
+    }
+
+    // `StoredExtendedRewards` of `solana-storage-proto`, then the legacy layout, only the one
+    // that consumes the whole buffer is valid
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes();
+    let rewards = match options.deserialize::<StoredExtendedRewards>(buffer) {
+        Ok(rewards) => rewards.into_iter().map(Into::into).collect(),
+        Err(error) => options
+            .deserialize::<LegacyRewards>(buffer)
+            .map_err(|_| DecodeError::Bincode("rewards", error))?
+            .into_iter()
+            .map(|(pubkey, lamports)| Reward {
+                pubkey,
+                lamports,
+                post_balance: 0,
+                reward_type: None,
+                commission: None,
+            })
+            .collect(),
+    };
+    Ok((rewards, None))
+}
+
+/// Staking and voting rewards of a pubkey summed over the epoch
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RewardTotals {
+    pub staking: i64,
+    pub voting: i64,
+    /// Balance after the last pushed reward
+    pub post_balance: u64,
+    /// Commission of the last pushed voting or staking reward
+    pub commission: Option<u8>,
+}
+
+/// Epoch-level aggregation of staking and voting rewards per pubkey, fee and rent rewards are
+/// ignored
+#[derive(Debug, Default, Clone)]
+pub struct EpochRewards {
+    pub rewards: HashMap<String, RewardTotals>,
+}
+
+impl EpochRewards {
+    /// Push rewards of a block of the epoch, partitioned rewards are spread over several blocks
+    pub fn push(&mut self, rewards: &[Reward]) {
+        for reward in rewards {
+            let totals = match reward.reward_type {
+                Some(RewardType::Staking) => {
+                    let totals = self.rewards.entry(reward.pubkey.clone()).or_default();
+                    totals.staking += reward.lamports;
+                    totals
+                }
+                Some(RewardType::Voting) => {
+                    let totals = self.rewards.entry(reward.pubkey.clone()).or_default();
+                    totals.voting += reward.lamports;
+                    totals
+                }
+                Some(RewardType::Fee | RewardType::Rent) | None => continue,
+            };
+            totals.post_balance = reward.post_balance;
+            totals.commission = reward.commission;
+        }
+    }
+
+    pub fn get(&self, pubkey: &str) -> Option<&RewardTotals> {
+        self.rewards.get(pubkey)
+    }
+}
+
+/// Reassembled and decompressed payload, `None` if the payload is empty; frames are streamed to
+/// the decoder unless the resolver caches payloads
+async fn decompress_dataframes<R: NodeResolver>(
+    kind: &'static str,
+    resolver: &R,
+    dataframe: &DataFrame,
+) -> Result<Option<Vec<u8>>, DecodeError> {
+    if resolver.payload_cache().is_some() {
+        let payload = resolver::reassemble_payload(resolver, dataframe).await?;
+        if payload.is_empty() {
+            return Ok(None);
+        }
+        return zstd::decode_all(payload.as_ref())
+            .map(Some)
+            .map_err(|error| DecodeError::Zstd(kind, error));
+    }
+
+    let reader = resolver::dataframe_reader(resolver, dataframe).await?;
+    if reader.is_empty() {
+        reader.finish()?;
+        return Ok(None);
+    }
+    decompress(kind, reader).map(Some)
+}
+
+/// Decompress the payload while frames are read, a hash mismatch is reported as
+/// `DecodeError::Reassable` even if corrupted payload fails decompression first
+fn decompress(kind: &'static str, reader: DataFrameReader<'_>) -> Result<Vec<u8>, DecodeError> {
+    let mut buffer = vec![];
+    let mut decoder = zstd::Decoder::new(reader).map_err(|error| DecodeError::Zstd(kind, error))?;
+    let error = match decoder.read_to_end(&mut buffer) {
+        Ok(_size) => return Ok(buffer),
+        Err(error) => error,
+    };
+    match error.downcast::<ReassableError>() {
+        Ok(error) => Err(DecodeError::Reassable(error)),
+        Err(error) => {
+            decoder.finish().into_inner().finish()?;
+            Err(DecodeError::Zstd(kind, error))
+        }
+    }
+}
+
+pub(crate) fn parse_transaction_status_meta(
+    buffer: &[u8],
+) -> Result<TransactionStatusMeta, DecodeError> {
+    match generated::TransactionStatusMeta::decode(buffer) {
+        Ok(meta) => TransactionStatusMeta::try_from(meta),
+        Err(_) => bincode::deserialize::<StoredTransactionStatusMeta>(buffer).map(Into::into),
+    }
+    .map_err(|error| DecodeError::Bincode("transaction metadata", error))
+}
+
+pub(crate) fn get_hash(hash: &[u8]) -> Result<Hash, DecodeError> {
+    <[u8; 32]>::try_from(hash)
+        .map(Hash::new_from_array)
+        .map_err(|_| DecodeError::InvalidHashLength(hash.len()))
+}
+
+const fn get_block_time(block: &Block) -> Option<UnixTimestamp> {
+    // zero blocktime is used for blocks produced before blocktime was recorded
+    match block.meta.blocktime {
+        0 => None,
+        blocktime => Some(blocktime as UnixTimestamp),
+    }
+}
+
+const fn check_encoding(encoding: UiTransactionEncoding) -> Result<(), DecodeError> {
+    match encoding {
+        UiTransactionEncoding::Json
+        | UiTransactionEncoding::JsonParsed
+        | UiTransactionEncoding::Base58
+        | UiTransactionEncoding::Base64 => Ok(()),
+        UiTransactionEncoding::Binary => Err(DecodeError::UnsupportedEncoding(encoding)),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("block node not found")]
+    MissedBlock,
+    #[error("block {0} has no entries")]
+    MissedEntries(u64),
+    #[error("missed cid: {0}")]
+    MissedCid(Cid),
+    #[error("invalid node kind: {node:?} (expected: {expected:?})")]
+    InvalidNode { node: Kind, expected: Kind },
+    #[error("invalid hash length: {0} (expected 32)")]
+    InvalidHashLength(usize),
+    #[error("transaction without metadata is not a legacy transaction")]
+    MissedMetadata,
+    #[error(transparent)]
+    Node(#[from] NodeError),
+    #[error(transparent)]
+    Reassable(#[from] ReassableError),
+    #[error("failed to decompress {0}: {1}")]
+    Zstd(&'static str, io::Error),
+    #[error("failed to decode {0}: {1}")]
+    Bincode(&'static str, bincode::Error),
+    #[error("unsupported encoding: {0:?}")]
+    UnsupportedEncoding(UiTransactionEncoding),
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+}
+
+impl DecodeError {
+    pub(crate) const fn invalid_node(node: &Node, expected: Kind) -> Self {
+        Self::InvalidNode {
+            node: node.kind(),
+            expected,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use {
+        crate::{
+            node::{Block, DataFrame, Entry, Node, Nodes, ReassableError, SlotMeta, Transaction},
+            resolver,
+            solana::{
+                BlockhashTracker, DecodeError, EpochRewards, RewardTotals, get_transaction,
+                parse_rewards,
+            },
+            util::tests::{
+                decode_cid, decode_hex, encode_block, encode_car, encode_cid, encode_entry,
+                encode_transaction,
+            },
+        },
+        crc::{CRC_64_GO_ISO, Crc},
+        prost::Message,
+        solana_sdk::hash::Hash,
+        solana_storage_proto::{StoredExtendedReward, convert::generated},
+        solana_transaction_status::{
+            BlockEncodingOptions, EncodedTransaction, Reward, RewardType, TransactionDetails,
+            UiTransactionEncoding,
+        },
+    };
+
+    const TX_DATA: &str = "0186d331474ac0e7cb3c57b2f80c3272d681b62cdb9b30381a22a91f08fee19adf289bbec7297aedf8d903a367d4ff1b839ed5dce9ee6559945b2c7c79221d1308010003050519b878d66540b318cc869f2241c41b76c29f0d1f21963e66ab7f8ad9c62ea70519b86ca395d378c9f90207463a258b4251cc3e5503eebbb6386d6492e4234a06a7d517192f0aafc6f265e3fb77cc7ada82c529d0be3b136e2d00552000000006a7d51718c774c928566398691d5eb68b5eb8a39b4b6d5c73555b21000000000761481d357474bb7c4d7624ebd3bdb3d8355e73d11043fc0da3538000000000b63ccf219e96d69095a25e439c0c0b064cf01397d8f6792d5822cad9f0e8f10b010404010203003d0200000002000000000000007d140101000000007e14010100000000f2ab07b3930cc2f69326873efa418252fc869fda1ddabf127a1793282935b85800";
+    const TX_METADATA: &str = "28b52ffd040075010022420710d047013f3dd2289ffd137a292b8ff27d609cbda5855e0e11eafdc17c0500a77a08325e41d6ce1c6a285fededc4e2";
+    const ENTRY_HASH: &str = "87b3f95ad785a5e8c7b5ffae44b37c200c27d5464870545489560c217a48d798";
+
+    pub fn create_nodes() -> Nodes {
+        let tx_cid = decode_cid("bafyreibysst7x3lvzdrllbspoob5z2epcrb6bmzqqlcxxysvku4cmvdk4e");
+        let entry_cid = decode_cid("bafyreieoodp5usfhjplhph653vpvkpys24meiyz3kvcoacj653yqoqganu");
+        let block_cid = decode_cid("bafyreig6fq5m25b736egoexujhstvhejjj5m5im6i6vlfvwr4ogcukfmny");
+
+        let mut nodes = Nodes::default();
+        nodes.nodes.insert(
+            tx_cid,
+            Node::Transaction(Transaction {
+                data: DataFrame {
+                    data: decode_hex(TX_DATA),
+                    ..Default::default()
+                },
+                metadata: DataFrame {
+                    data: decode_hex(TX_METADATA),
+                    ..Default::default()
+                },
+                slot: 16848004,
+                index: Some(0),
+            }),
+        );
+        nodes.nodes.insert(
+            entry_cid,
+            Node::Entry(Entry {
+                num_hashes: 12179,
+                hash: decode_hex(ENTRY_HASH),
+                transactions: vec![tx_cid],
+            }),
+        );
+        nodes.nodes.insert(
+            block_cid,
+            Node::Block(Block {
+                slot: 16848004,
+                shredding: vec![],
+                entries: vec![entry_cid],
+                meta: SlotMeta {
+                    parent_slot: 16848003,
+                    blocktime: 1593047427,
+                    block_height: None,
+                },
+                rewards: decode_cid("bafkqaaa"),
+            }),
+        );
+        nodes
+    }
+
+    /// Nodes of `create_nodes` (with the parent and the block time of `encode_block`) as CAR
+    pub fn create_car() -> Vec<u8> {
+        let tx = encode_transaction(&decode_hex(TX_DATA), &decode_hex(TX_METADATA), 16848004, 0);
+        let tx_cid = encode_cid(&tx);
+        let entry = encode_entry(12179, &decode_hex(ENTRY_HASH), &[tx_cid]);
+        let entry_cid = encode_cid(&entry);
+        let block = encode_block(16848004, &[entry_cid]);
+        encode_car(&[
+            (tx_cid, tx),
+            (entry_cid, entry),
+            (encode_cid(&block), block),
+        ])
+    }
+
+    #[test]
+    fn test_rewards() {
+        let reward = |reward_type, lamports| Reward {
+            pubkey: "Vote111111111111111111111111111111111111111".to_owned(),
+            lamports,
+            post_balance: 1000,
+            reward_type: Some(reward_type),
+            commission: Some(5),
+        };
+
+        let protobuf = generated::Rewards {
+            rewards: vec![reward(RewardType::Voting, 10).into()],
+            num_partitions: Some(generated::NumPartitions { num_partitions: 4 }),
+        }
+        .encode_to_vec();
+        assert_eq!(
+            parse_rewards(&protobuf).unwrap(),
+            (vec![reward(RewardType::Voting, 10)], Some(4))
+        );
+
+        let pubkey = || "Vote111111111111111111111111111111111111111".to_owned();
+        let stored = (0..2)
+            .map(|_| StoredExtendedReward::from(reward(RewardType::Staking, 7)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parse_rewards(&bincode::serialize(&stored).unwrap()).unwrap(),
+            (vec![reward(RewardType::Staking, 7); 2], None)
+        );
+        let legacy = Reward {
+            pubkey: pubkey(),
+            lamports: 7,
+            post_balance: 0,
+            reward_type: None,
+            commission: None,
+        };
+        assert_eq!(
+            parse_rewards(&bincode::serialize(&vec![(pubkey(), 7i64); 2]).unwrap()).unwrap(),
+            (vec![legacy; 2], None)
+        );
+
+        let mut epoch = EpochRewards::default();
+        epoch.push(&[
+            reward(RewardType::Voting, 10),
+            reward(RewardType::Staking, 20),
+            reward(RewardType::Fee, 30),
+        ]);
+        epoch.push(&[reward(RewardType::Staking, 5)]);
+        assert_eq!(
+            epoch.get(&pubkey()),
+            Some(&RewardTotals {
+                staking: 25,
+                voting: 10,
+                post_balance: 1000,
+                commission: Some(5),
+            })
+        );
+
+        // only the identity CID of an empty block means no rewards
+        let nodes = create_nodes();
+        let block = nodes.get_block().unwrap();
+        assert!(matches!(nodes.get_rewards(&block.rewards), Ok(None)));
+        let missed = encode_cid(b"rewards");
+        assert!(matches!(
+            nodes.get_rewards(&missed),
+            Err(DecodeError::MissedCid(cid)) if cid == missed
+        ));
+    }
+
+    #[test]
+    fn test_decode_entries() {
+        let nodes = create_nodes();
+        let block = nodes.get_block().unwrap();
+        let entries = nodes.decode_block_entries(block).expect("valid entries");
+        let entry = nodes.get_entry(&block.entries[0]).unwrap();
+        let tx = nodes
+            .decode_versioned_transaction(nodes.get_transaction(&entry.transactions[0]).unwrap())
+            .unwrap();
+        assert_eq!(
+            entries,
+            vec![solana_entry::entry::Entry {
+                num_hashes: 12179,
+                hash: "A8jD8bJVZUALsEjK654LW3NX5Pidt8GCevHydMNzXvN3"
+                    .parse()
+                    .unwrap(),
+                transactions: vec![tx],
+            }]
+        );
+        assert_eq!(
+            entries[0].transactions[0].signatures[0].to_string(),
+            "3hLwbGQetenDLR5sq3j1gemN2u8TA12knQTDa8NZoDC4tBrdSX22yEP8noNTEFjPHGSmpQAyqt97V3BXsMX1o3fu"
+        );
+
+        // the same transaction resolved by cid
+        let cid = entry.transactions[0];
+        let transaction = resolver::resolved(get_transaction(&nodes, &cid)).unwrap();
+        assert_eq!(
+            resolver::resolved(transaction.decode_versioned(&nodes)).unwrap(),
+            entries[0].transactions[0]
+        );
+    }
+
+    #[test]
+    fn test_corrupted_metadata() {
+        // larger than the decoder buffer, so decompression fails before the end of the payload
+        let mut state = 1u64;
+        let meta = (0..1 << 19)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (state >> 59) as u8
+            })
+            .collect::<Vec<_>>();
+        let payload = zstd::encode_all(meta.as_slice(), 0).unwrap();
+        let hash = Crc::<u64>::new(&CRC_64_GO_ISO).checksum(&payload);
+
+        // payload is split into three frames, the middle one is corrupted
+        let chunks = payload
+            .chunks(payload.len().div_ceil(3))
+            .collect::<Vec<_>>();
+        let frame = |index: usize| DataFrame {
+            hash: (index == 0).then_some(hash),
+            index: Some(index as u64),
+            total: Some(3),
+            data: chunks[index].to_vec(),
+            next: vec![],
+        };
+        let cids = [encode_cid(b"frame-1"), encode_cid(b"frame-2")];
+        let transaction = Transaction {
+            metadata: DataFrame {
+                next: cids.to_vec(),
+                ..frame(0)
+            },
+            ..Default::default()
+        };
+        let mut nodes = Nodes::default();
+        for (cid, index) in cids.into_iter().zip(1..) {
+            nodes.nodes.insert(cid, Node::DataFrame(frame(index)));
+        }
+        assert_eq!(
+            nodes
+                .decompress_transaction_status_meta(&transaction)
+                .unwrap(),
+            Some(meta)
+        );
+
+        let Some(Node::DataFrame(middle)) = nodes.nodes.get_mut(&cids[0]) else {
+            unreachable!();
+        };
+        middle.data.fill(0xff);
+        assert!(matches!(
+            nodes.decompress_transaction_status_meta(&transaction),
+            Err(DecodeError::Reassable(ReassableError::InvalidHash { expected, .. }))
+                if expected == hash
+        ));
+    }
+
+    #[test]
+    fn test_blockhash_tracker() {
+        let nodes = create_nodes();
+        let blockhash = "A8jD8bJVZUALsEjK654LW3NX5Pidt8GCevHydMNzXvN3"
+            .parse::<Hash>()
+            .unwrap();
+
+        let mut tracker = BlockhashTracker::new();
+        let hashes = tracker.push(&nodes).expect("valid block");
+        assert_eq!(hashes.slot, 16848004);
+        assert_eq!(hashes.blockhash, blockhash);
+        assert_eq!(hashes.previous_blockhash, None);
+        assert_eq!(tracker.previous(), Some((16848004, blockhash)));
+
+        let parent = Hash::new_unique();
+        let mut tracker = BlockhashTracker::with_previous(16848003, parent);
+        let block = tracker.get_confirmed_block(&nodes).expect("valid block");
+        assert_eq!(block.previous_blockhash, parent.to_string());
+        assert_eq!(block.blockhash, blockhash.to_string());
+    }
+
+    #[test]
+    fn test_encode() {
+        let nodes = create_nodes();
+
+        let block = nodes
+            .encode_block(
+                Hash::default(),
+                UiTransactionEncoding::Base64,
+                BlockEncodingOptions {
+                    transaction_details: TransactionDetails::Full,
+                    show_rewards: true,
+                    max_supported_transaction_version: None,
+                },
+            )
+            .expect("valid block");
+        assert_eq!(
+            block.blockhash,
+            "A8jD8bJVZUALsEjK654LW3NX5Pidt8GCevHydMNzXvN3"
+        );
+        assert_eq!(block.previous_blockhash, Hash::default().to_string());
+        assert_eq!(block.parent_slot, 16848003);
+        assert_eq!(block.block_time, Some(1593047427));
+        let transactions = block.transactions.expect("transactions");
+        assert_eq!(transactions.len(), 1);
+        let Some(meta) = transactions[0].meta.as_ref() else {
+            panic!("expected transaction meta");
+        };
+        assert_eq!(meta.fee, 5000);
+        assert!(matches!(
+            transactions[0].transaction,
+            EncodedTransaction::Binary(_, _)
+        ));
+
+        let signature = nodes
+            .decode_versioned_transaction(
+                nodes
+                    .get_block_transactions(nodes.get_block().unwrap())
+                    .unwrap()[0],
+            )
+            .expect("valid transaction")
+            .signatures[0];
+        let tx = nodes
+            .encode_transaction(&signature, UiTransactionEncoding::Json, Some(0))
+            .expect("valid transaction")
+            .expect("transaction exists");
+        assert_eq!(tx.slot, 16848004);
+        assert_eq!(tx.block_time, Some(1593047427));
+
+        assert!(
+            nodes
+                .encode_transaction(&signature, UiTransactionEncoding::Binary, None)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use {
+    crate::util,
+    std::{future::Future, io, path::PathBuf},
+    tokio::{
+        fs::File,
+        io::{AsyncSeekExt, SeekFrom},
+    },
+};
+
+/// Random access reads of CAR file bytes
+pub trait CarSource {
+    /// Read exactly `length` bytes starting from `offset`
+    fn read_at(&self, offset: u64, length: u64)
+    -> impl Future<Output = io::Result<Vec<u8>>> + Send;
+}
+
+/// Local file, opened on every read so the source can be shared between tasks
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CarSource for FileSource {
+    async fn read_at(&self, offset: u64, length: u64) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        util::read_exact(&mut file, length as usize).await
+    }
+}
+
+/// File behind HTTP server with range requests support
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+#[cfg(feature = "http")]
+impl HttpSource {
+    pub fn new(url: reqwest::Url) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    pub const fn with_client(client: reqwest::Client, url: reqwest::Url) -> Self {
+        Self { client, url }
+    }
+}
+
+#[cfg(feature = "http")]
+impl CarSource for HttpSource {
+    async fn read_at(&self, offset: u64, length: u64) -> io::Result<Vec<u8>> {
+        if length == 0 {
+            return Ok(vec![]);
+        }
+
+        let response = self
+            .client
+            .get(self.url.clone())
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes={}-{}", offset, offset + length - 1),
+            )
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(io::Error::other)?;
+        // full body means that the server ignored the range
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(io::Error::other(format!(
+                "range requests are not supported: {}",
+                response.status()
+            )));
+        }
+
+        let body = response.bytes().await.map_err(io::Error::other)?;
+        if body.len() as u64 != length {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("expected {length} bytes, received {}", body.len()),
+            ));
+        }
+        Ok(body.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            index::SlotIndex,
+            node::{Node, NodeError, Nodes},
+            source::{CarSource, FileSource},
+            util::tests::{TempFile, decode_hex, encode_block, encode_car, encode_cid},
+        },
+        std::io::Cursor,
+    };
+
+    async fn create_index() -> (Vec<u8>, SlotIndex) {
+        let entry = decode_hex(
+            "84011930d458203a43cd82e140873740fde924da4125ac30e2fec5eb92344dbb2bb4776973feec80",
+        );
+        let entry_cid = encode_cid(&entry);
+        let block = encode_block(1, &[entry_cid]);
+        let car = encode_car(&[(entry_cid, entry), (encode_cid(&block), block)]);
+        let index = SlotIndex::build(Cursor::new(&car), |_, _| Ok::<(), NodeError>(()))
+            .await
+            .expect("valid CAR");
+        (car, index)
+    }
+
+    async fn check_source<S: CarSource>(index: &SlotIndex, source: &S) {
+        let nodes: Nodes = index
+            .fetch_block(source, 1)
+            .await
+            .expect("valid block")
+            .expect("block exists");
+        assert!(matches!(
+            nodes.nodes.values().last(),
+            Some(Node::Block(block)) if block.slot == 1
+        ));
+        assert!(index.fetch_block(source, 2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_source() {
+        let (car, index) = create_index().await;
+        let file = TempFile::new("car-source", &car);
+        check_source(&index, &FileSource::new(&file.0)).await;
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_source() {
+        use {
+            crate::source::HttpSource,
+            tokio::{
+                io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+                net::TcpListener,
+            },
+        };
+
+        let (car, index) = create_index().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/epoch-0.car", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("range: bytes=") {
+                        let (start, end) = value.split_once('-').unwrap();
+                        range = Some(
+                            start.parse::<usize>().unwrap()..end.parse::<usize>().unwrap() + 1,
+                        );
+                    }
+                }
+
+                let range = range.expect("range request");
+                let body = &car[range.clone()];
+                let head = format!(
+                    "HTTP/1.1 206 Partial Content\r\ncontent-length: {}\r\ncontent-range: bytes {}-{}/{}\r\nconnection: close\r\n\r\n",
+                    body.len(),
+                    range.start,
+                    range.end - 1,
+                    car.len()
+                );
+                let stream = stream.get_mut();
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(body).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+
+        check_source(&index, &HttpSource::new(url.parse().unwrap())).await;
+    }
+}
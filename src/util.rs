@@ -1,22 +1,42 @@
 use {
-    std::io,
+    std::{
+        future::Future,
+        io,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    },
     tokio::io::{AsyncRead, AsyncReadExt},
 };
 
+/// Initial capacity of `read_exact` buffer, it grows only with received data
+const READ_EXACT_CAPACITY: usize = 64 * 1024;
+
+/// Read exactly `size` bytes, a bogus size from untrusted input can not allocate more memory
+/// than the input actually contains
 #[inline]
 pub async fn read_exact<R: AsyncRead + Unpin>(reader: &mut R, size: usize) -> io::Result<Vec<u8>> {
-    let mut buffer = Vec::with_capacity(size);
-    // SAFETY: All `size` bytes would be initialized by read_exact
-    #[allow(clippy::uninit_vec)]
-    unsafe {
-        buffer.set_len(size)
+    let mut buffer = Vec::with_capacity(size.min(READ_EXACT_CAPACITY));
+    reader.take(size as u64).read_to_end(&mut buffer).await?;
+    if buffer.len() != size {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "early eof"));
     }
-    reader.read_exact(&mut buffer).await?;
     Ok(buffer)
 }
 
+/// Poll the future once, e.g. to resolve in-memory nodes from sync code
+pub(crate) fn now_or_never<F: Future>(future: F) -> Option<F::Output> {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
 pub mod cbor {
-    use {crate::node::NodeError, cid::Cid, serde_cbor::Value};
+    use {
+        crate::node::{DecodeMode, LinkError, NodeError},
+        cid::Cid,
+        serde_cbor::Value,
+    };
 
     #[inline]
     pub fn get_array(value: Value, path: &'static str) -> Result<Vec<Value>, NodeError> {
@@ -29,6 +49,35 @@ pub mod cbor {
         }
     }
 
+    /// Fields of the node in tuple representation, in strict mode the first `required` fields
+    /// should be present and no values are allowed after the last field
+    #[inline]
+    pub fn get_fields(
+        value: Value,
+        path: &'static str,
+        fields: &[&'static str],
+        required: usize,
+        mode: DecodeMode,
+    ) -> Result<Vec<Value>, NodeError> {
+        let values = get_array(value, path)?;
+        if mode == DecodeMode::Strict {
+            if values.len() < required {
+                return Err(NodeError::MissedField {
+                    path,
+                    field: fields[values.len()],
+                });
+            }
+            if values.len() > fields.len() {
+                return Err(NodeError::UnexpectedFieldCount {
+                    path,
+                    count: values.len(),
+                    max: fields.len(),
+                });
+            }
+        }
+        Ok(values)
+    }
+
     #[inline]
     pub fn get_array_opt(
         value: Value,
@@ -67,6 +116,45 @@ pub mod cbor {
         }
     }
 
+    /// Integer as `u64`, out of range values are truncated in lenient mode
+    #[inline]
+    pub fn get_u64(value: Value, path: &'static str, mode: DecodeMode) -> Result<u64, NodeError> {
+        get_int(value, path).and_then(|value| to_u64(value, path, mode))
+    }
+
+    #[inline]
+    pub fn get_u64_opt(
+        value: Value,
+        path: &'static str,
+        mode: DecodeMode,
+    ) -> Result<Option<u64>, NodeError> {
+        get_int_opt(value, path)?
+            .map(|value| to_u64(value, path, mode))
+            .transpose()
+    }
+
+    /// Integer as `i64`, out of range values are truncated in lenient mode
+    #[inline]
+    pub fn get_i64(value: Value, path: &'static str, mode: DecodeMode) -> Result<i64, NodeError> {
+        let value = get_int(value, path)?;
+        match mode {
+            DecodeMode::Lenient => Ok(value as i64),
+            DecodeMode::Strict => {
+                i64::try_from(value).map_err(|_| NodeError::IntegerOutOfRange { path, value })
+            }
+        }
+    }
+
+    #[inline]
+    fn to_u64(value: i128, path: &'static str, mode: DecodeMode) -> Result<u64, NodeError> {
+        match mode {
+            DecodeMode::Lenient => Ok(value as u64),
+            DecodeMode::Strict => {
+                u64::try_from(value).map_err(|_| NodeError::IntegerOutOfRange { path, value })
+            }
+        }
+    }
+
     #[inline]
     pub fn get_bytes(value: Value, path: &'static str) -> Result<Vec<u8>, NodeError> {
         match value {
@@ -78,9 +166,34 @@ pub mod cbor {
         }
     }
 
+    /// CBOR tag of IPLD links
+    const CID_TAG: u64 = 42;
+    /// Multibase prefix of binary CIDs in DAG-CBOR links
+    const MULTIBASE_IDENTITY: u8 = 0x00;
+
+    /// Link is a tag 42 byte string with the identity multibase prefix, the prefix is optional
+    /// because older tooling wrote raw CID bytes (no CID starts with 0x00)
     #[inline]
     pub fn get_cid(value: Value, path: &'static str) -> Result<Cid, NodeError> {
-        Cid::try_from(&get_bytes(value, path)?[1..]).map_err(Into::into)
+        decode_link(value).map_err(|error| NodeError::InvalidLink { path, error })
+    }
+
+    #[inline]
+    fn decode_link(value: Value) -> Result<Cid, LinkError> {
+        let bytes = match value {
+            Value::Tag(CID_TAG, value) => match *value {
+                Value::Bytes(bytes) => bytes,
+                _ => return Err(LinkError::ExpectedBytes),
+            },
+            Value::Tag(tag, _) => return Err(LinkError::UnexpectedTag(Some(tag))),
+            _ => return Err(LinkError::UnexpectedTag(None)),
+        };
+        let cid = match bytes.split_first() {
+            Some((&MULTIBASE_IDENTITY, cid)) => cid,
+            Some(_) => bytes.as_slice(),
+            None => return Err(LinkError::Empty),
+        };
+        Cid::try_from(cid).map_err(Into::into)
     }
 
     #[inline]
@@ -96,9 +209,83 @@ pub mod cbor {
     }
 }
 
+#[cfg(feature = "serde")]
+pub mod serde {
+    use {
+        base64::{Engine, engine::general_purpose::STANDARD},
+        cid::Cid,
+        serde::{Serializer, ser::SerializeSeq},
+    };
+
+    #[inline]
+    pub fn cid<S: Serializer>(cid: &Cid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(cid)
+    }
+
+    #[inline]
+    pub fn cids<S: Serializer>(cids: &[Cid], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(cids.len()))?;
+        for cid in cids {
+            seq.serialize_element(&cid.to_string())?;
+        }
+        seq.end()
+    }
+
+    #[inline]
+    pub fn base58<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bs58::encode(bytes).into_string())
+    }
+
+    #[inline]
+    pub fn base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
-    use {cid::Cid, const_hex::decode};
+    use {
+        cid::Cid,
+        const_hex::decode,
+        multihash::Multihash,
+        serde_cbor::Value,
+        sha2::{Digest, Sha256},
+        std::{collections::BTreeMap, fs, path::PathBuf, process},
+    };
+
+    /// File in the temp directory, removed on drop so a failed test does not leave it behind
+    pub struct TempFile(pub PathBuf);
+
+    impl TempFile {
+        pub fn new(name: &str, data: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("{name}-{}.car", process::id()));
+            fs::write(&path, data).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Directory in the temp directory, removed with its files on drop
+    pub struct TempDir(pub PathBuf);
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("{name}-{}", process::id()));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
 
     pub fn decode_hex<T: AsRef<[u8]>>(input: T) -> Vec<u8> {
         decode(input).unwrap()
@@ -111,4 +298,113 @@ pub mod tests {
     pub fn decode_cids<const N: usize>(cids: [&'static str; N]) -> Vec<Cid> {
         cids.into_iter().map(decode_cid).collect()
     }
+
+    /// CIDv1 DAG-CBOR with sha2-256 of the data
+    pub fn encode_cid(data: &[u8]) -> Cid {
+        let hash = Multihash::wrap(0x12, &Sha256::digest(data)).unwrap();
+        Cid::new_v1(0x71, hash)
+    }
+
+    pub fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+        while value >= 0x80 {
+            buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        buf.push(value as u8);
+    }
+
+    pub fn encode_link(cid: &Cid) -> Value {
+        let mut bytes = vec![0];
+        bytes.extend(cid.to_bytes());
+        Value::Tag(42, Box::new(Value::Bytes(bytes)))
+    }
+
+    pub fn encode_section(cid: &Cid, data: &[u8], buf: &mut Vec<u8>) {
+        let cid = cid.to_bytes();
+        encode_varint((cid.len() + data.len()) as u64, buf);
+        buf.extend(cid);
+        buf.extend(data);
+    }
+
+    /// CARv1 with the last section as the root, same as Old Faithful epoch files
+    pub fn encode_car(sections: &[(Cid, Vec<u8>)]) -> Vec<u8> {
+        let header = serde_cbor::to_vec(&Value::Map(BTreeMap::from([
+            (
+                Value::Text("roots".to_owned()),
+                Value::Array(
+                    sections
+                        .last()
+                        .map(|(cid, _)| encode_link(cid))
+                        .into_iter()
+                        .collect(),
+                ),
+            ),
+            (Value::Text("version".to_owned()), Value::Integer(1)),
+        ])))
+        .unwrap();
+
+        let mut car = vec![];
+        encode_varint(header.len() as u64, &mut car);
+        car.extend(header);
+        for (cid, data) in sections {
+            encode_section(cid, data, &mut car);
+        }
+        car
+    }
+
+    pub fn encode_epoch(epoch: u64, subsets: &[Cid]) -> Vec<u8> {
+        serde_cbor::to_vec(&Value::Array(vec![
+            Value::Integer(4),
+            Value::Integer(epoch.into()),
+            Value::Array(subsets.iter().map(encode_link).collect()),
+        ]))
+        .unwrap()
+    }
+
+    pub fn encode_dataframe(data: &[u8]) -> Value {
+        Value::Array(vec![
+            Value::Integer(6),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Bytes(data.to_vec()),
+        ])
+    }
+
+    pub fn encode_transaction(data: &[u8], metadata: &[u8], slot: u64, index: u64) -> Vec<u8> {
+        serde_cbor::to_vec(&Value::Array(vec![
+            Value::Integer(0),
+            encode_dataframe(data),
+            encode_dataframe(metadata),
+            Value::Integer(slot.into()),
+            Value::Integer(index.into()),
+        ]))
+        .unwrap()
+    }
+
+    pub fn encode_entry(num_hashes: u64, hash: &[u8], transactions: &[Cid]) -> Vec<u8> {
+        serde_cbor::to_vec(&Value::Array(vec![
+            Value::Integer(1),
+            Value::Integer(num_hashes.into()),
+            Value::Bytes(hash.to_vec()),
+            Value::Array(transactions.iter().map(encode_link).collect()),
+        ]))
+        .unwrap()
+    }
+
+    pub fn encode_block(slot: u64, entries: &[Cid]) -> Vec<u8> {
+        serde_cbor::to_vec(&Value::Array(vec![
+            Value::Integer(2),
+            Value::Integer(slot.into()),
+            Value::Array(vec![]),
+            Value::Array(entries.iter().map(encode_link).collect()),
+            Value::Array(vec![
+                Value::Integer(slot.saturating_sub(1).into()),
+                Value::Integer(0),
+                Value::Null,
+            ]),
+            encode_link(&decode_cid("bafkqaaa")),
+        ]))
+        .unwrap()
+    }
 }
@@ -18,6 +18,14 @@ const MAX_VARINT_LEN_64: usize = 10;
 
 #[inline]
 pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<u64, VarIntError> {
+    read_with_len(reader).await.map(|(value, _len)| value)
+}
+
+/// Reads varint, returning the value and the number of bytes read.
+#[inline]
+pub async fn read_with_len<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<(u64, usize), VarIntError> {
     let mut x = 0u64;
     let mut s = 0u32;
     for i in 0..MAX_VARINT_LEN_64 {
@@ -26,7 +34,7 @@ pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<u64, VarIntErr
             return if i == MAX_VARINT_LEN_64 - 1 && b > 1 {
                 Err(VarIntError::Invalid)
             } else {
-                Ok(x | ((b as u64) << s))
+                Ok((x | ((b as u64) << s), i + 1))
             };
         }
         x |= ((b & 0x7f) as u64) << s;